@@ -17,7 +17,8 @@
 //! 2.  **Annotate it with `#[derive(RsHtml)]`**.
 //! 3.  **Create a template file** (e.g., `home.rs.html`) using `@` prefixed
 //!     expressions, control flow (`@if`, `@for`), components, layouts, etc.
-//! 4.  **Call the generated `render()` method** on an instance of your struct and get the HTML output.
+//! 4.  **Call the generated `render()` method** on an instance of your struct and get the HTML output,
+//!     or stream it with `write_to_io()` into any `std::io::Write`.
 //!
 //! ## Key Syntax Highlights
 //!
@@ -26,6 +27,7 @@
 //! - **Code Blocks:** `@{ let a = 5; }`
 //! - **Layouts/Sections:** `@extends`, `@section`, `@render`
 //! - **Components:** `<MyComponent data=@value />` or `@MyComponent { ... }`
+//! - **Nested Templates:** `@self.sidebar` renders another `#[derive(RsHtml)]` struct in place
 //! - **Raw Blocks:** `@raw{ ... }`
 //! - **Comments:** `@* server-side comment *@`
 //!
@@ -97,8 +99,15 @@
 //!
 //! - **[`RsHtml` (derive macro)]**: The main entry point. Apply to a struct to
 //!   enable template rendering. It handles parsing the associated template file
-//!   (path can be customized via `#[rshtml(path = "...")]`) and generates
-//!   the rendering logic.
+//!   (path can be customized via `#[rshtml(path = "...")]`, or the template written
+//!   inline with `#[rshtml(source = "...")]`) and generates the rendering logic.
+//! - **[`rshtml!`]**: Compiles an inline template into a renderable value that
+//!   borrows the local variables it uses, handy for small widgets and tests.
+//! - **[`template`]**: Turns a function into a template, with its arguments in
+//!   scope instead of `self`, for partials that don't need a struct.
+//! - Rendering takes `&self`, so a template can be shared behind an `Arc` or
+//!   rendered as a `Box<dyn RsHtml + Send + Sync>`. Templates that change their
+//!   own fields opt in with `#[rshtml(mut)]` and implement `RsHtmlMut` instead.
 //!
 //! ---
 //!
@@ -111,6 +120,26 @@
 pub use rshtml_core::functions;
 pub use rshtml_core::traits;
 
+/// Runtime escaping used by the generated rendering code.
+pub use rshtml_core::escape;
+
+/// Trusted HTML that is written into templates without escaping.
+pub use rshtml_core::markup::Markup;
+
+/// The value returned by [`rshtml!`] and by [`template`] functions.
+pub use rshtml_core::template_fn::TemplateFn;
+
+/// Compiles an inline template, e.g. `rshtml!(r#"<p>@name</p>"#)`, into a [`traits::Render`] value
+/// that borrows the local variables it uses.
+pub use rshtml_macro::rshtml;
+
+/// Turns a function into a template, with the arguments in scope instead of `self`:
+/// `#[template("user_row.rs.html")] fn user_row(user: &User, highlight: bool) -> impl Render {}`.
+///
+/// The declared return type is replaced with a [`TemplateFn`], which can be rendered with `render()`
+/// or written into another template as `@user_row(&user, true)`.
+pub use rshtml_macro::template;
+
 /// The primary derive macro for enabling RsHtml templating on a struct.
 ///
 /// Apply `#[derive(RsHtml)]` to a Rust struct to associate it with an
@@ -121,9 +150,10 @@ pub use rshtml_core::traits;
 /// By default, the macro attempts to find a template file named after the
 /// struct (e.g., `HomePage` struct maps to `home.rs.html`).
 /// This path can be customized using the `#[rshtml(path = "custom.rs.html")]` attribute
-/// on the struct.
+/// on the struct, or the template given inline with `#[rshtml(source = r#"..."#)]`.
 ///
 /// Once derived, an instance of the struct will have a `render()` method to produce the HTML output.
+/// With `#[rshtml(mut)]` the template may mutate `self` and `traits::RsHtmlMut` is implemented instead.
 pub use rshtml_macro::RsHtml;
 
 use rshtml_core::config;
@@ -149,10 +179,10 @@ fn walk_dir(dir: &Path) {
             let path = entry.path();
             if path.is_dir() {
                 walk_dir(&path);
-            } else if path.is_file() {
-                if let Some(path_str) = path.to_str() {
-                    println!("cargo:rerun-if-changed={}", path_str);
-                }
+            } else if path.is_file()
+                && let Some(path_str) = path.to_str()
+            {
+                println!("cargo:rerun-if-changed={}", path_str);
             }
         }
     }
@@ -1,5 +1,6 @@
 mod component;
 mod extends_directive;
+mod html_context;
 mod inner_text;
 mod match_expr;
 mod raw;
@@ -11,12 +12,14 @@ mod rust_expr_paren;
 mod rust_expr_simple;
 mod section_block;
 mod section_directive;
+mod slot_directive;
 mod text;
 mod use_directive;
 
 use crate::Node;
-use crate::compiler::component::ComponentCompiler;
+use crate::compiler::component::{ComponentCompiler, ContentPlaces, SharedFunction};
 use crate::compiler::extends_directive::ExtendsDirectiveCompiler;
+use crate::compiler::html_context::HtmlContext;
 use crate::compiler::inner_text::InnerTextCompiler;
 use crate::compiler::match_expr::MatchExprCompiler;
 use crate::compiler::raw::RawCompiler;
@@ -28,23 +31,37 @@ use crate::compiler::rust_expr_paren::RustExprParenCompiler;
 use crate::compiler::rust_expr_simple::RustExprSimpleCompiler;
 use crate::compiler::section_block::SectionBlockCompiler;
 use crate::compiler::section_directive::SectionDirectiveCompiler;
+use crate::compiler::slot_directive::SlotDirectiveCompiler;
 use crate::compiler::text::TextCompiler;
 use crate::compiler::use_directive::UseDirectiveCompiler;
-use anyhow::Result;
-use proc_macro2::TokenStream;
-use quote::quote;
-use std::collections::HashMap;
+use crate::diagnostic::Diagnostic;
+use crate::escape::{Context, escape};
+use crate::node::Position;
+use anyhow::{Result, anyhow};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{format_ident, quote};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-// TODO: Maybe use like syn::parse2::<Expr> for compiler control, and get error from parser
+use std::str::FromStr;
 
 pub struct Compiler {
     use_directives: Vec<(String, PathBuf)>,
     components: HashMap<String, Node>,
+    /// The components imported with `@use inline`, compiled again at each call.
+    inline_components: HashSet<String>,
+    /// The function each component is compiled into, by the component and the types of its parameters.
+    component_functions: HashMap<String, SharedFunction>,
+    /// The functions of the components, written once before the body of the template.
+    pub component_definitions: TokenStream,
     layout_directive: PathBuf,
     pub layout: Option<Node>,
     sections: HashMap<String, TokenStream>,
+    /// Where each component being compiled writes its child content and slots, innermost last.
+    content_places: Vec<ContentPlaces>,
     pub section_body: Option<TokenStream>,
     pub text_size: usize,
+    html_context: HtmlContext,
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl Compiler {
@@ -52,21 +69,30 @@ impl Compiler {
         Compiler {
             use_directives: Vec::new(),
             components: HashMap::new(),
+            inline_components: HashSet::new(),
+            component_functions: HashMap::new(),
+            component_definitions: TokenStream::new(),
             layout_directive: PathBuf::new(),
             layout: None,
             sections: HashMap::new(),
+            content_places: Vec::new(),
             section_body: None,
             text_size: 0,
+            html_context: HtmlContext::new(),
+            diagnostics: Vec::new(),
         }
     }
 
     pub fn compile(&mut self, node: &Node) -> Result<TokenStream> {
         match node {
             Node::Template(nodes) => {
+                // an error only fails its own node, so the independent errors of a template are all reported
                 let mut token_stream = TokenStream::new();
                 for node in nodes {
-                    let ts = self.compile(node)?;
-                    token_stream.extend(quote! {#ts});
+                    match self.compile(node) {
+                        Ok(ts) => token_stream.extend(quote! {#ts}),
+                        Err(err) => self.diagnostics.push(Diagnostic::from(err)),
+                    }
                 }
                 Ok(token_stream)
             }
@@ -75,20 +101,29 @@ impl Compiler {
             Node::Comment(_) => Ok(quote! {}),
             Node::ExtendsDirective(path, layout) => ExtendsDirectiveCompiler::compile(self, path, layout),
             Node::RenderDirective(name) => RenderDirectiveCompiler::compile(self, name),
-            Node::RustBlock(contents) => RustBlockCompiler::compile(self, contents),
-            Node::RustExprSimple(expr, is_escaped) => RustExprSimpleCompiler::compile(self, expr, is_escaped),
-            Node::RustExprParen(expr, is_escaped) => RustExprParenCompiler::compile(self, expr, is_escaped),
-            Node::MatchExpr(name, arms) => MatchExprCompiler::compile(self, name, arms),
-            Node::RustExpr(exprs) => RustExprCompiler::compile(self, exprs),
+            Node::RustBlock(contents, position) => RustBlockCompiler::compile(self, contents, position),
+            Node::RustExprSimple(expr, is_escaped, position) => RustExprSimpleCompiler::compile(self, expr, is_escaped, position),
+            Node::RustExprParen(expr, is_escaped, position) => RustExprParenCompiler::compile(self, expr, is_escaped, position),
+            Node::MatchExpr(name, arms, position) => MatchExprCompiler::compile(self, name, arms, position),
+            Node::RustExpr(exprs, position) => RustExprCompiler::compile(self, exprs, position),
             Node::SectionDirective(name, content) => SectionDirectiveCompiler::compile(self, name, content),
             Node::SectionBlock(name, content) => SectionBlockCompiler::compile(self, name, content),
             Node::RenderBody => RenderBodyCompiler::compile(self),
-            Node::Component(name, parameters, body) => ComponentCompiler::compile(self, name, parameters, body),
-            Node::ChildContent => Ok(quote! {child_content(__f__)?;}),
+            Node::Component(name, parameters, body, position) => ComponentCompiler::compile(self, name, parameters, body, position),
+            Node::ChildContent => {
+                ComponentCompiler::record_content_place(self, None, None)?;
+                Ok(quote! {__child_content__(__f__)?;})
+            }
+            Node::Slot(name, _, position) => {
+                Err(Diagnostic::new(format!("<slot:{}> must be directly in the body of a component", name), position.clone()).into())
+            }
+            Node::SlotDirective(name, position) => SlotDirectiveCompiler::compile(self, name, position),
+            Node::PropsDirective(..) => Ok(quote! {}),
             Node::Raw(body) => RawCompiler::compile(self, body),
-            Node::UseDirective(name, path, component) => UseDirectiveCompiler::compile(self, name, path, component),
+            Node::UseDirective(name, path, component, inline) => UseDirectiveCompiler::compile(self, name, path, component, *inline),
             Node::ContinueDirective => Ok(quote! {continue;}),
             Node::BreakDirective => Ok(quote! {break;}),
+            Node::FlushDirective => Ok(quote! {__flush__()?;}),
         }
     }
 
@@ -99,28 +134,113 @@ impl Compiler {
         quote! {[#token_stream]}
     }
 
-    fn escape(&self, input: TokenStream) -> TokenStream {
+    fn render(&self, input: TokenStream, context: Context) -> TokenStream {
+        let context_ts = match context {
+            Context::Raw => quote! {Raw},
+            Context::Html => quote! {Html},
+            Context::Attribute => quote! {Attribute},
+            Context::UnquotedAttribute => quote! {UnquotedAttribute},
+            Context::Url => quote! {Url},
+            Context::UrlPart => quote! {UrlPart},
+            Context::Script => quote! {Script},
+            Context::RawScript => quote! {RawScript},
+            Context::JsString => quote! {JsString},
+            Context::Css => quote! {Css},
+        };
+
         quote! {
-            for c in #input.to_string().chars() {
-                match c {
-                    '&' => write!(__f__, "{}", "&amp;")?,
-                    '<' => write!(__f__, "{}", "&lt;")?,
-                    '>' => write!(__f__, "{}", "&gt;")?,
-                    '"' => write!(__f__, "{}", "&quot;")?,
-                    '\'' => write!(__f__, "{}", "&#39;")?,
-                    '/' => write!(__f__, "{}", "&#x2F;")?,
-                    _ => write!(__f__, "{}", c)?,
-                }
+            (&&rshtml::traits::Dispatch(&#input)).__rshtml_render_to(__f__, rshtml::escape::Context::#context_ts, __flush__)?;
+        }
+    }
+
+    /// Writes static text with a single `write_str` on a `&'static str`.
+    fn write_text(&mut self, text: &str) -> TokenStream {
+        if text.is_empty() {
+            return quote! {};
+        }
+
+        self.text_size += text.len();
+        quote! { __f__.write_str(#text)?; }
+    }
+
+    /// Escapes a literal expression like `@("abc")` while compiling, for the context it is written into.
+    fn escape_or_raw_literal(&mut self, value: &str, is_escaped: &bool) -> Result<TokenStream> {
+        let context = self.html_context.expression();
+        let context = Self::raw_context(context, is_escaped);
+
+        let mut escaped = String::new();
+        escape(&mut escaped, context, value).map_err(|err| anyhow!("Escape Error: {}", err))?;
+
+        Ok(self.write_text(&escaped))
+    }
+
+    /// Lexes a piece of Rust code from the template, reporting errors at its position.
+    fn rust_code(&self, code: &str, position: &Position) -> Result<TokenStream> {
+        TokenStream::from_str(code).map_err(|err| Diagnostic::new(format!("Lex Error: {}", err), position.clone()).into())
+    }
+
+    /// Parses a snippet of the template as a `syn` node, so malformed code is reported at its template position
+    /// instead of failing inside the generated code. `code_ts` is the snippet, completed where it is not a whole node by itself.
+    fn validate<T: syn::parse::Parse>(&self, code_ts: TokenStream, snippet: &str, kind: &str, position: &Position) -> Result<()> {
+        syn::parse2::<T>(code_ts).map(|_| ()).map_err(|err| {
+            let mut snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+            if snippet.chars().count() > 80 {
+                snippet = snippet.chars().take(77).collect::<String>() + "...";
             }
+
+            Diagnostic::new(format!("Invalid Rust {} `{}`: {}", kind, snippet, err), position.clone()).into()
+        })
+    }
+
+    /// Wraps an expression of the template in a macro named after its position, like `__rshtml_home_rs_html_42_17`, and
+    /// returns the macro's definition, to be written before the statement using it, and the call in place of the
+    /// expression. rustc can't point into the template, but it ends the errors in the expression with "this error
+    /// originates in the macro `__rshtml_home_rs_html_42_17`". `part` tells apart the expressions of a node.
+    ///
+    /// Only whole expressions are wrapped, since what a macro binds isn't seen outside it, and not those with a `$`.
+    fn at_position(&self, expr_ts: TokenStream, position: &Position, part: &str) -> (TokenStream, TokenStream) {
+        fn has_dollar(ts: &TokenStream) -> bool {
+            ts.clone().into_iter().any(|tt| match tt {
+                TokenTree::Punct(punct) => punct.as_char() == '$',
+                TokenTree::Group(group) => has_dollar(&group.stream()),
+                _ => false,
+            })
+        }
+
+        if has_dollar(&expr_ts) {
+            return (TokenStream::new(), expr_ts);
         }
+
+        let name = match part {
+            "" => format!("__rshtml_{}", Self::position_name(position)),
+            part => format!("__rshtml_{}_{}", Self::position_name(position), part),
+        };
+        let name = format_ident!("{}", name.replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_"));
+
+        (quote! { macro_rules! #name { () => { #expr_ts } } }, quote! { #name!() })
+    }
+
+    /// The file name, line and column of `position`, like `home.rs.html_42_17`, for the names of generated items.
+    fn position_name(position: &Position) -> String {
+        let file = std::path::Path::new(&position.file)
+            .file_name()
+            .map_or(String::new(), |file| file.to_string_lossy().to_string());
+        format!("{}_{}_{}", file, position.line, position.column)
     }
 
-    fn escape_or_raw(&self, expr_ts: TokenStream, is_escaped: &bool) -> TokenStream {
-        if *is_escaped {
-            let escaped = self.escape(quote! {(#expr_ts)});
-            quote! {#escaped}
-        } else {
-            quote! {write!(__f__, "{}", #expr_ts)?;}
+    /// The context of an expression written with `@#`, which is only escaped where it could end a `<script>`.
+    fn raw_context(context: Context, is_escaped: &bool) -> Context {
+        match (is_escaped, context) {
+            (true, context) => context,
+            (false, Context::Script) => Context::RawScript,
+            (false, _) => Context::Raw,
         }
     }
+
+    fn escape_or_raw(&mut self, expr_ts: TokenStream, is_escaped: &bool) -> TokenStream {
+        let context = self.html_context.expression();
+        let context = Self::raw_context(context, is_escaped);
+
+        self.render(quote! {(#expr_ts)}, context)
+    }
 }
@@ -1,63 +1,705 @@
 use crate::Node;
 use crate::compiler::Compiler;
-use crate::node::{ComponentParameter, ComponentParameterValue};
-use anyhow::{Result, anyhow};
-use proc_macro2::TokenStream;
-use quote::quote;
+use crate::compiler::html_context::HtmlContext;
+use crate::diagnostic::Diagnostic;
+use crate::node::{ComponentParameter, ComponentParameterValue, ComponentProp, Position, RustBlockContent};
+use crate::optimizer::Optimizer;
+use anyhow::Result;
+use proc_macro2::{Group, Ident, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote};
+use std::collections::HashMap;
 use std::ops::AddAssign;
 use std::str::FromStr;
 
+const INTEGER_TYPES: [&str; 12] = ["i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize"];
+const FLOAT_TYPES: [&str; 2] = ["f32", "f64"];
+const STRING_TYPES: [&str; 2] = ["&str", "String"];
+
 pub struct ComponentCompiler;
 
+/// The function a component is compiled into, shared by its calls in html text with the same parameters.
+#[derive(Clone)]
+pub struct SharedFunction {
+    name: Ident,
+    /// The size of the text it writes.
+    text_size: usize,
+    /// The html context it ends in.
+    end: HtmlContext,
+    places: ContentPlaces,
+}
+
+/// Where a component writes the content its calls give, in the html.
+#[derive(Clone, Default)]
+pub struct ContentPlaces {
+    /// The position of the call compiling the component.
+    call: Position,
+    child_content: Option<HtmlContext>,
+    slots: HashMap<String, HtmlContext>,
+}
+
 impl ComponentCompiler {
-    pub fn compile(compiler: &mut Compiler, name: &str, parameters: &Vec<ComponentParameter>, body: &[Node]) -> Result<TokenStream> {
-        let component_node = compiler.components.get(name).ok_or(anyhow!("Component {} not found", name))?;
-        let component_node = component_node.clone();
+    /// Compiles a component call into a call of the function the component is compiled into, given the parameters, the
+    /// child content and the slots. The component sees only what it is given, not the `self` or the variables of the
+    /// template that calls it.
+    ///
+    /// The function is written once at the top of the template and shared by the calls in html text with the same
+    /// parameters. A call elsewhere, like in a `<script>`, is compiled on its own for its context, and a component
+    /// imported with `@use inline` is compiled again at each call, with its literal parameters written into its markup.
+    /// So is a component without `@props`, see [`Self::untyped_call`].
+    ///
+    /// The child content and the slots are compiled after the component, for the place in its html where it writes them,
+    /// and the caller goes on from where the component ends.
+    pub fn compile(
+        compiler: &mut Compiler,
+        name: &str,
+        parameters: &[ComponentParameter],
+        body: &[Node],
+        position: &Position,
+    ) -> Result<TokenStream> {
+        let component_node = compiler
+            .components
+            .get(name)
+            .ok_or_else(|| Diagnostic::new(format!("Component {} not found", name), position.clone()))?;
+        let props = Self::props(component_node).map(|(props, props_position)| (props.to_vec(), props_position.clone()));
+        Self::check_self(name, component_node)?;
+
+        for (i, parameter) in parameters.iter().enumerate() {
+            if parameters[..i].iter().any(|other| other.name == parameter.name) {
+                return Err(Diagnostic::new(
+                    format!("the parameter `{}` of component {} is given twice", parameter.name, name),
+                    position.clone(),
+                )
+                .into());
+            }
+        }
 
-        let mut token_stream = TokenStream::new();
+        let inline = compiler.inline_components.contains(name);
+        let component_node = match inline {
+            true => Optimizer::inline_literal_parameters(component_node, parameters),
+            false => component_node.clone(),
+        };
 
-        for parameter in parameters {
-            let name_ts = TokenStream::from_str(&parameter.name).map_err(|err| anyhow!("Lex Error: {}", err))?;
-
-            let parameter_ts = match &parameter.value {
-                ComponentParameterValue::Bool(value) => quote! {let #name_ts = #value;},
-                ComponentParameterValue::Number(value) => {
-                    compiler.text_size.add_assign(value.len());
-                    quote! {let #name_ts = #value;}
-                }
-                ComponentParameterValue::String(value) => {
-                    compiler.text_size.add_assign(value.len());
-                    quote! {let #name_ts = #value;}
-                }
-                ComponentParameterValue::RustExprParen(value) => {
-                    let expr_ts = TokenStream::from_str(value).map_err(|err| anyhow!("Lex Error: {}", err))?;
-                    quote! {let #name_ts = #expr_ts;}
-                }
-                ComponentParameterValue::RustExprSimple(value) => {
-                    let expr_ts = TokenStream::from_str(value).map_err(|err| anyhow!("Lex Error: {}", err))?;
-                    quote! {let #name_ts = #expr_ts;}
-                }
-                ComponentParameterValue::Block(value) => {
-                    let block_ts = compiler.compile(&Node::Template(value.clone()))?;
-                    quote! {
-                        let mut #name_ts = String::new();
-                        (|__f__: &mut dyn ::std::fmt::Write| -> ::std::fmt::Result {#block_ts Ok(())})(&mut #name_ts)?;
+        let mut parameters_ts = TokenStream::new();
+        let mut arguments_ts = TokenStream::new();
+        // written before the call: the checks of the props and the macros wrapping the arguments
+        let mut items_ts = TokenStream::new();
+        let mut defaults_ts = TokenStream::new();
+        let mut signature = Vec::new();
+
+        if let Some((props, props_position)) = &props {
+            Self::check_parameters(compiler, name, props, props_position, parameters, position)?;
+
+            // in the order they are declared, a prop with a default is an `Option`, `None` when the call doesn't give it
+            for prop in props {
+                let argument_ts = match parameters.iter().find(|parameter| parameter.name == prop.name) {
+                    Some(parameter) => {
+                        let argument_ts = Self::compile_argument(compiler, Some(prop), parameter, position, &mut items_ts)?;
+                        Some(Self::check_prop_type(
+                            compiler,
+                            name,
+                            prop,
+                            argument_ts,
+                            props_position,
+                            position,
+                            &mut items_ts,
+                        )?)
+                    }
+                    None => None,
+                };
+
+                let name_ts = compiler.rust_code(&prop.name, props_position)?;
+                let ty_ts = compiler.rust_code(&prop.ty, props_position)?;
+
+                match (&prop.default, argument_ts) {
+                    (Some(default), argument_ts) => {
+                        // evaluated in the component, where it sees the props declared before it and not the caller's variables
+                        let default_ts = compiler.rust_code(default, props_position)?;
+                        compiler.validate::<syn::Expr>(default_ts.clone(), default, "expression", props_position)?;
+                        defaults_ts.extend(quote! {let #name_ts: #ty_ts = #name_ts.unwrap_or_else(|| #default_ts);});
+
+                        signature.push(format!("{}: Option<{}>", prop.name, ty_ts));
+                        parameters_ts.extend(quote! {#name_ts: Option<#ty_ts>,});
+                        match argument_ts {
+                            Some(argument_ts) => arguments_ts.extend(quote! {Some(#argument_ts),}),
+                            None => arguments_ts.extend(quote! {None,}),
+                        }
+                    }
+                    (None, Some(argument_ts)) => {
+                        signature.push(format!("{}: {}", prop.name, ty_ts));
+                        parameters_ts.extend(quote! {#name_ts: #ty_ts,});
+                        arguments_ts.extend(quote! {#argument_ts,});
                     }
+                    (None, None) => {}
                 }
+            }
+        } else {
+            // without `@props` the component is compiled at each call, see `untyped_call`
+            for parameter in parameters {
+                let name_ts = compiler.rust_code(&parameter.name, position)?;
+                let argument_ts = Self::compile_argument(compiler, None, parameter, position, &mut items_ts)?;
+                parameters_ts.extend(quote! {#name_ts,});
+                arguments_ts.extend(quote! {#argument_ts,});
+            }
+        }
+
+        let mut component_slots = Vec::new();
+        Self::collect_slots(&component_node, &mut component_slots);
+
+        let mut slots = HashMap::new();
+        let mut child_nodes = Vec::new();
+        for node in body {
+            let Node::Slot(slot_name, slot_body, slot_position) = node else {
+                child_nodes.push(node.clone());
+                continue;
+            };
+
+            if slots.contains_key(slot_name) {
+                return Err(Diagnostic::new(
+                    format!("the slot `{}` of component {} is given twice", slot_name, name),
+                    slot_position.clone(),
+                )
+                .into());
+            }
+            if !component_slots.contains(slot_name) {
+                let names = component_slots.iter().map(|slot| format!("`{}`", slot)).collect::<Vec<_>>().join(", ");
+                let message = match names.as_str() {
+                    "" => format!("component {} has no slot `{}`, it renders no @slot", name, slot_name),
+                    _ => format!("component {} has no slot `{}`, its slots are {}", name, slot_name, names),
+                };
+                return Err(Diagnostic::new(message, slot_position.clone()).into());
+            }
+
+            slots.insert(slot_name.clone(), (slot_body.clone(), slot_position.clone()));
+        }
+
+        // every slot the component renders is a parameter, `None` when the call doesn't give it
+        let slot_idents = component_slots.iter().map(|slot| format_ident!("__slot_{}__", slot)).collect::<Vec<_>>();
+        let content_ts = |compiler: &mut Compiler, places: &ContentPlaces| -> Result<(TokenStream, Vec<TokenStream>)> {
+            let what = format!("the child content of component {}", name);
+            let body_ts = Self::compile_content(compiler, child_nodes.clone(), places.child_content.as_ref(), &what, position)?;
+
+            let mut slot_arguments_ts = Vec::new();
+            for slot_name in &component_slots {
+                slot_arguments_ts.push(match slots.get(slot_name) {
+                    Some((slot_body, slot_position)) => {
+                        let what = format!("the slot `{}` of component {}", slot_name, name);
+                        let slot_ts = Self::compile_content(compiler, slot_body.clone(), places.slots.get(slot_name), &what, slot_position)?;
+                        quote! {Some(&|__f__| -> ::std::fmt::Result {#slot_ts Ok(())})}
+                    }
+                    None => quote! {None},
+                });
+            }
+
+            Ok((quote! {&|__f__| -> ::std::fmt::Result {#body_ts Ok(())}}, slot_arguments_ts))
+        };
+
+        if props.is_none() {
+            let (component_ts, places) = Self::compile_component(compiler, &component_node, position);
+            let (child_content_ts, slot_arguments_ts) = content_ts(compiler, &places)?;
+
+            return Ok(Self::untyped_call(
+                &items_ts,
+                &child_content_ts,
+                &slot_idents,
+                &slot_arguments_ts,
+                &component_slots,
+                parameters_ts,
+                arguments_ts,
+                component_ts?,
+            ));
+        }
+
+        for slot_ident in &slot_idents {
+            parameters_ts.extend(quote! {#slot_ident: Option<&dyn Fn(&mut __W) -> ::std::fmt::Result>,});
+        }
+        let call_ts = |function: &Ident, child_content_ts: TokenStream, slot_arguments_ts: Vec<TokenStream>| {
+            quote! {#function(__f__, __flush__, #child_content_ts, #arguments_ts #(#slot_arguments_ts,)*)?;}
+        };
+
+        // a shared function is compiled for html text, where the template starts; called anywhere else, like in a
+        // `<script>`, the component is compiled for that call, in the context of the call
+        let shared = !inline && compiler.html_context.is_text();
+
+        let key = format!("{}({})", name, signature.join(", "));
+        if shared && let Some(function) = compiler.component_functions.get(&key).cloned() {
+            // the call goes on in html text from where the component ends, as the function was compiled from html text
+            compiler.text_size.add_assign(function.text_size);
+            compiler.html_context = function.end;
+            let (child_content_ts, slot_arguments_ts) = content_ts(compiler, &function.places)?;
+            let call_ts = call_ts(&function.name, child_content_ts, slot_arguments_ts);
+            return Ok(quote! {{ #items_ts #call_ts }});
+        }
+
+        let function = match shared {
+            true => format_ident!("__component_{}_{}__", name, compiler.component_functions.len()),
+            false => format_ident!("__component__"),
+        };
+
+        // a shared function is compiled from html text, not from the context of the first call, which is html text too
+        if shared {
+            compiler.html_context = HtmlContext::new();
+        }
+        let text_size = compiler.text_size;
+        let (component_ts, places) = Self::compile_component(compiler, &component_node, position);
+        let component_ts = component_ts?;
+
+        let function_ts = quote! {
+            #[allow(unused_variables, clippy::too_many_arguments, clippy::unnecessary_lazy_evaluations)]
+            fn #function<__W: ::std::fmt::Write + ?Sized>(
+                __f__: &mut __W,
+                __flush__: &dyn Fn() -> ::std::fmt::Result,
+                __child_content__: &dyn Fn(&mut __W) -> ::std::fmt::Result,
+                #parameters_ts
+            ) -> ::std::fmt::Result {
+                let has_slot = |name: &str| -> bool { [#((#component_slots, #slot_idents.is_some())),*].contains(&(name, true)) };
+                #defaults_ts
+                #component_ts
+                Ok(())
+            }
+        };
+
+        if shared {
+            let shared_function = SharedFunction {
+                name: function.clone(),
+                text_size: compiler.text_size - text_size,
+                end: compiler.html_context.clone(),
+                places: places.clone(),
             };
+            compiler.component_functions.insert(key, shared_function);
+        }
+
+        let (child_content_ts, slot_arguments_ts) = content_ts(compiler, &places)?;
+        let call_ts = call_ts(&function, child_content_ts, slot_arguments_ts);
 
-            token_stream.extend(parameter_ts);
+        if !shared {
+            return Ok(quote! {{ #items_ts #function_ts #call_ts }});
         }
 
-        let body_ts = compiler.compile(&Node::Template(body.to_owned()))?;
-        let body_ts = quote! {let child_content = |__f__: &mut dyn ::std::fmt::Write| -> ::std::fmt::Result {#body_ts  Ok(())};};
+        compiler.component_definitions.extend(function_ts);
 
-        token_stream.extend(body_ts);
+        Ok(quote! {{ #items_ts #call_ts }})
+    }
+
+    /// Compiles the markup of a component, from the current html context, returning where it writes its child content
+    /// and its slots.
+    fn compile_component(compiler: &mut Compiler, component_node: &Node, position: &Position) -> (Result<TokenStream>, ContentPlaces) {
+        let places = ContentPlaces {
+            call: position.clone(),
+            child_content: None,
+            slots: HashMap::new(),
+        };
+        compiler.content_places.push(places);
+        let component_ts = compiler.compile(component_node);
+        let places = compiler.content_places.pop().unwrap_or_default();
 
-        let component_ts = compiler.compile(&component_node);
+        (component_ts, places)
+    }
 
-        token_stream.extend(component_ts);
+    /// Compiles content given by a call, like the child content, for the place in the html the component writes it,
+    /// or for html text if it doesn't. It must end where it starts, since the markup of the component goes on from there.
+    /// The context of the caller is left as it is.
+    fn compile_content(
+        compiler: &mut Compiler,
+        nodes: Vec<Node>,
+        place: Option<&HtmlContext>,
+        what: &str,
+        position: &Position,
+    ) -> Result<TokenStream> {
+        let start = place.cloned().unwrap_or_else(HtmlContext::new);
+        let caller = std::mem::replace(&mut compiler.html_context, start.clone());
+        let content_ts = compiler.compile(&Node::Template(nodes));
+        let end = std::mem::replace(&mut compiler.html_context, caller);
+        let content_ts = content_ts?;
 
-        Ok(quote! {{ #token_stream }})
+        if !end.is_same_place(&start) {
+            let message = format!(
+                "{} ends in another place of the html than it starts, close the tags, comments and quotes it opens",
+                what
+            );
+            return Err(Diagnostic::new(message, position.clone()).into());
+        }
+
+        Ok(content_ts)
+    }
+
+    /// Records the place in the html where the component being compiled writes its child content, or the slot `slot`.
+    /// Content given by a call is compiled for one place, so a component writing it in two different places is reported,
+    /// at `position` or else at the call.
+    pub fn record_content_place(compiler: &mut Compiler, slot: Option<&str>, position: Option<&Position>) -> Result<()> {
+        let context = compiler.html_context.clone();
+        let Some(places) = compiler.content_places.last_mut() else {
+            return Ok(());
+        };
+
+        let (place, directive) = match slot {
+            Some(slot) => (
+                places.slots.entry(slot.to_string()).or_insert_with(|| context.clone()),
+                format!("@slot(\"{}\")", slot),
+            ),
+            None => (places.child_content.get_or_insert_with(|| context.clone()), "@child_content".to_string()),
+        };
+        if !place.is_same_place(&context) {
+            let message = format!(
+                "{} is written in two different places of the html, like in text and in an attribute, write it in one",
+                directive
+            );
+            return Err(Diagnostic::new(message, position.unwrap_or(&places.call).clone()).into());
+        }
+
+        Ok(())
     }
+
+    /// The call of a component without `@props`, whose parameters take the types of the values given, like
+    /// `items=@v.iter()` that it loops over. It is compiled at each call into a closure taking the parameters, passed to a
+    /// function along with the values so that their types are known when the closure is checked. The closure is given
+    /// mixed-site spans, which hide the variables of the caller from it as a function of its own would.
+    #[allow(clippy::too_many_arguments)]
+    fn untyped_call(
+        items_ts: &TokenStream,
+        child_content_ts: &TokenStream,
+        slot_idents: &[Ident],
+        slot_arguments_ts: &[TokenStream],
+        component_slots: &[String],
+        parameters_ts: TokenStream,
+        arguments_ts: TokenStream,
+        component_ts: TokenStream,
+    ) -> TokenStream {
+        let slot_type_ts = quote! {Option<&dyn Fn(&mut __W) -> ::std::fmt::Result>};
+        let slot_types_ts = slot_idents.iter().map(|_| &slot_type_ts).collect::<Vec<_>>();
+
+        let closure_ts = respan(
+            quote! {
+                |__f__, __flush__, __child_content__, #(#slot_idents,)* (#parameters_ts)| -> ::std::fmt::Result {
+                    let has_slot = |name: &str| -> bool { [#((#component_slots, #slot_idents.is_some())),*].contains(&(name, true)) };
+                    #component_ts
+                    Ok(())
+                }
+            },
+            Span::mixed_site(),
+        );
+
+        quote! {{
+            #items_ts
+            #[allow(clippy::too_many_arguments)]
+            fn __component__<__W: ::std::fmt::Write + ?Sized, __A>(
+                __f__: &mut __W,
+                __flush__: &dyn Fn() -> ::std::fmt::Result,
+                __child_content__: &dyn Fn(&mut __W) -> ::std::fmt::Result,
+                #(#slot_idents: #slot_types_ts,)*
+                __arguments__: __A,
+                __component__: impl FnOnce(
+                    &mut __W,
+                    &dyn Fn() -> ::std::fmt::Result,
+                    &dyn Fn(&mut __W) -> ::std::fmt::Result,
+                    #(#slot_types_ts,)*
+                    __A
+                ) -> ::std::fmt::Result,
+            ) -> ::std::fmt::Result {
+                __component__(__f__, __flush__, __child_content__, #(#slot_idents,)* __arguments__)
+            }
+            #[allow(unused_variables)]
+            let __result__ = __component__(__f__, __flush__, #child_content_ts, #(#slot_arguments_ts,)* (#arguments_ts), #closure_ts);
+            __result__?;
+        }}
+    }
+
+    /// The value passed for a parameter, evaluated where the component is called. An expression is wrapped by
+    /// [`Compiler::at_position`], with its macro added to `items_ts`.
+    fn compile_argument(
+        compiler: &mut Compiler,
+        prop: Option<&ComponentProp>,
+        parameter: &ComponentParameter,
+        position: &Position,
+        items_ts: &mut TokenStream,
+    ) -> Result<TokenStream> {
+        let argument_ts = match &parameter.value {
+            ComponentParameterValue::Bool(value) => quote! {#value},
+            ComponentParameterValue::Number(value) => {
+                compiler.text_size.add_assign(value.len());
+                match prop {
+                    Some(prop) => {
+                        // `ratio=2` is `2.0` for an `f64` prop
+                        let needs_fraction = FLOAT_TYPES.contains(&prop.ty.as_str()) && !value.contains('.');
+                        let number = if needs_fraction { format!("{}.0", value) } else { value.to_string() };
+                        compiler.rust_code(&number, position)?
+                    }
+                    None => quote! {#value},
+                }
+            }
+            ComponentParameterValue::String(value) => {
+                compiler.text_size.add_assign(value.len());
+                match prop {
+                    Some(prop) if prop.ty == "String" => quote! {String::from(#value)},
+                    _ => quote! {#value},
+                }
+            }
+            ComponentParameterValue::RustExprParen(value) | ComponentParameterValue::RustExprSimple(value) => {
+                let expr_ts = compiler.rust_code(value, position)?;
+                compiler.validate::<syn::Expr>(expr_ts.clone(), value, "expression", position)?;
+                let (definition_ts, expr_ts) = compiler.at_position(expr_ts, position, parameter.name.trim_start_matches("r#"));
+                items_ts.extend(definition_ts);
+                expr_ts
+            }
+            ComponentParameterValue::Block(value) => {
+                // rendered where the component writes it, as is, since the block was escaped for html text when it was compiled
+                let what = format!("the parameter `{}`", parameter.name);
+                let block_ts = Self::compile_content(compiler, value.clone(), None, &what, position)?;
+                quote! {
+                    rshtml::TemplateFn::new(
+                        |__f__: &mut dyn ::std::fmt::Write, __flush__: &dyn Fn() -> ::std::fmt::Result| -> ::std::fmt::Result {#block_ts Ok(())}
+                    )
+                }
+            }
+        };
+
+        Ok(argument_ts)
+    }
+
+    /// Passes the value given for `prop` through a tuple struct named after the prop and the call, like
+    /// `__prop_count_Alert_page_rs_html_3_1`, defined in `checks_ts`. rustc can't point into the template, but the error
+    /// for a value of the wrong type names that struct. `impl Trait` props are left to the component's function.
+    fn check_prop_type(
+        compiler: &mut Compiler,
+        name: &str,
+        prop: &ComponentProp,
+        argument_ts: TokenStream,
+        props_position: &Position,
+        position: &Position,
+        checks_ts: &mut TokenStream,
+    ) -> Result<TokenStream> {
+        if prop.ty.starts_with("impl ") {
+            return Ok(argument_ts);
+        }
+
+        let check = format!(
+            "__prop_{}_{}_{}",
+            prop.name.trim_start_matches("r#"),
+            name,
+            Compiler::position_name(position)
+        )
+        .replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_");
+        let check = format_ident!("{}", check);
+        let ty_ts = compiler.rust_code(&prop.ty, props_position)?;
+
+        checks_ts.extend(quote! {
+            #[allow(non_camel_case_types)]
+            struct #check<T>(T);
+        });
+
+        Ok(quote! {{ let #check(value): #check<#ty_ts> = #check(#argument_ts); value }})
+    }
+
+    /// Reports the use of `self` in a component, which is a function of its own and can't see the caller's `self`.
+    fn check_self(name: &str, component: &Node) -> Result<()> {
+        match Self::find_self(component) {
+            Some(position) => Err(Diagnostic::new(
+                format!("component {} can't use `self`, pass what it needs as a parameter", name),
+                position.clone(),
+            )
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Where the Rust code in `node` first uses `self`.
+    fn find_self(node: &Node) -> Option<&Position> {
+        let nodes = match node {
+            Node::RustExprSimple(expr, _, position) | Node::RustExprParen(expr, _, position) => {
+                return uses_self(expr).then_some(position);
+            }
+            Node::RustBlock(contents, position) => {
+                return contents.iter().any(block_uses_self).then_some(position);
+            }
+            Node::RustExpr(clauses, position) if clauses.iter().any(|(head, _)| uses_self(head)) => return Some(position),
+            Node::MatchExpr(head, _, position) if uses_self(head) => return Some(position),
+            Node::PropsDirective(props, position) => {
+                return props
+                    .iter()
+                    .any(|prop| prop.default.as_deref().is_some_and(uses_self))
+                    .then_some(position);
+            }
+            Node::Template(nodes) | Node::SectionBlock(_, nodes) | Node::Slot(_, nodes, _) => nodes.iter().collect(),
+            Node::RustExpr(clauses, _) | Node::MatchExpr(_, clauses, _) => clauses.iter().flat_map(|(_, nodes)| nodes).collect(),
+            Node::Component(_, parameters, body, position) => {
+                let mut nodes = Vec::new();
+                for parameter in parameters {
+                    match &parameter.value {
+                        ComponentParameterValue::RustExprParen(expr) | ComponentParameterValue::RustExprSimple(expr) if uses_self(expr) => {
+                            return Some(position);
+                        }
+                        ComponentParameterValue::Block(block) => nodes.extend(block),
+                        _ => {}
+                    }
+                }
+                nodes.extend(body);
+                nodes
+            }
+            _ => Vec::new(),
+        };
+
+        nodes.into_iter().find_map(Self::find_self)
+    }
+
+    /// The names of the slots a component renders with `@slot`.
+    fn collect_slots(node: &Node, slots: &mut Vec<String>) {
+        let nodes = match node {
+            Node::SlotDirective(name, _) => {
+                if !slots.contains(name) {
+                    slots.push(name.clone());
+                }
+                return;
+            }
+            Node::Template(nodes) | Node::SectionBlock(_, nodes) | Node::Slot(_, nodes, _) => nodes.iter().collect(),
+            Node::RustExpr(clauses, _) | Node::MatchExpr(_, clauses, _) => clauses.iter().flat_map(|(_, nodes)| nodes).collect(),
+            Node::Component(_, parameters, body, _) => parameters
+                .iter()
+                .filter_map(|parameter| match &parameter.value {
+                    ComponentParameterValue::Block(nodes) => Some(nodes),
+                    _ => None,
+                })
+                .flatten()
+                .chain(body)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        for node in nodes {
+            Self::collect_slots(node, slots);
+        }
+    }
+
+    /// The props the component declares with `@props`, and where.
+    fn props(component: &Node) -> Option<(&[ComponentProp], &Position)> {
+        let Node::Template(nodes) = component else {
+            return None;
+        };
+
+        nodes.iter().find_map(|node| match node {
+            Node::PropsDirective(props, position) => Some((props.as_slice(), position)),
+            _ => None,
+        })
+    }
+
+    /// Checks the types of the props, then reports the parameters of a call that aren't props of the component, the props
+    /// without a default that are missing, and the literal values that can't have the type of their prop, all at the call site.
+    fn check_parameters(
+        compiler: &mut Compiler,
+        name: &str,
+        props: &[ComponentProp],
+        props_position: &Position,
+        parameters: &[ComponentParameter],
+        position: &Position,
+    ) -> Result<()> {
+        for prop in props {
+            if !prop.ty.starts_with("impl ") {
+                let ty_ts = compiler.rust_code(&prop.ty, props_position)?;
+                compiler.validate::<syn::Type>(ty_ts, &prop.ty, "type", props_position)?;
+            } else if prop.default.is_some() {
+                // the component can't make a value of a type its caller picks
+                let message = format!("the prop `{}: {}` of component {} can't have a default", prop.name, prop.ty, name);
+                return Err(Diagnostic::new(message, props_position.clone()).into());
+            }
+        }
+
+        let mut errors = Vec::new();
+
+        for parameter in parameters {
+            match props.iter().find(|prop| prop.name == parameter.name) {
+                None => {
+                    let names = props.iter().map(|prop| format!("`{}`", prop.name)).collect::<Vec<_>>().join(", ");
+                    errors.push(format!(
+                        "unknown prop `{}` for component {}, its props are {}",
+                        parameter.name, name, names
+                    ));
+                }
+                Some(prop) => {
+                    if let Some(value) = Self::mismatched_literal(&parameter.value, &prop.ty) {
+                        errors.push(format!(
+                            "the prop `{}` of component {} is a `{}`, got {}",
+                            prop.name, name, prop.ty, value
+                        ));
+                    }
+                }
+            }
+        }
+
+        for prop in props.iter().filter(|prop| prop.default.is_none()) {
+            if !parameters.iter().any(|parameter| parameter.name == prop.name) {
+                errors.push(format!("missing prop `{}: {}` for component {}", prop.name, prop.ty, name));
+            }
+        }
+
+        let mut diagnostics = errors
+            .into_iter()
+            .map(|error| Diagnostic::new(format!("{} (declared at {})", error, props_position), position.clone()));
+
+        let Some(first) = diagnostics.next() else {
+            return Ok(());
+        };
+        compiler.diagnostics.extend(diagnostics);
+
+        Err(first.into())
+    }
+
+    /// What a literal value is, when the prop's type is a primitive or string type it can't be. Blocks are written by a
+    /// closure, which is none of them. Other types are left to rustc.
+    fn mismatched_literal(value: &ComponentParameterValue, ty: &str) -> Option<&'static str> {
+        let ty = ty.split_whitespace().collect::<String>();
+        let ty = match ty.strip_prefix("&'") {
+            Some(lifetime) => lifetime.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_').to_string(),
+            None => ty,
+        };
+        let ty = if ty == "str" { "&str" } else { ty.as_str() };
+
+        let is_known = ty == "bool" || INTEGER_TYPES.contains(&ty) || FLOAT_TYPES.contains(&ty) || STRING_TYPES.contains(&ty);
+        if !is_known {
+            return None;
+        }
+
+        let (fits, value) = match value {
+            ComponentParameterValue::Bool(_) => (ty == "bool", "a bool"),
+            ComponentParameterValue::Number(number) if number.contains('.') => (FLOAT_TYPES.contains(&ty), "a decimal number"),
+            ComponentParameterValue::Number(number) if number.starts_with('-') && ty.starts_with('u') => (false, "a negative number"),
+            ComponentParameterValue::Number(_) => (INTEGER_TYPES.contains(&ty) || FLOAT_TYPES.contains(&ty), "a number"),
+            ComponentParameterValue::String(_) => (STRING_TYPES.contains(&ty), "a string"),
+            ComponentParameterValue::Block(_) => (false, "a block"),
+            ComponentParameterValue::RustExprParen(_) | ComponentParameterValue::RustExprSimple(_) => (true, ""),
+        };
+
+        (!fits).then_some(value)
+    }
+}
+
+/// Whether `code` has a `self` token, outside of strings.
+fn uses_self(code: &str) -> bool {
+    fn has_self(ts: TokenStream) -> bool {
+        ts.into_iter().any(|tt| match tt {
+            TokenTree::Ident(ident) => ident == "self",
+            TokenTree::Group(group) => has_self(group.stream()),
+            _ => false,
+        })
+    }
+
+    TokenStream::from_str(code).is_ok_and(has_self)
+}
+
+fn block_uses_self(content: &RustBlockContent) -> bool {
+    match content {
+        RustBlockContent::Code(code) => uses_self(code),
+        RustBlockContent::NestedBlock(contents) => contents.iter().any(block_uses_self),
+        _ => false,
+    }
+}
+
+/// `ts` with every token given `span`.
+fn respan(ts: TokenStream, span: Span) -> TokenStream {
+    ts.into_iter()
+        .map(|mut tt| {
+            if let TokenTree::Group(group) = &tt {
+                tt = TokenTree::Group(Group::new(group.delimiter(), respan(group.stream(), span)));
+            }
+            tt.set_span(span);
+            tt
+        })
+        .collect()
 }
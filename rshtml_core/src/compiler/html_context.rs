@@ -0,0 +1,305 @@
+use crate::escape::Context;
+
+const URL_ATTRIBUTES: [&str; 13] = [
+    "href",
+    "src",
+    "action",
+    "formaction",
+    "cite",
+    "data",
+    "poster",
+    "background",
+    "longdesc",
+    "usemap",
+    "manifest",
+    "ping",
+    "xlink:href",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Quote {
+    Double,
+    Single,
+    Unquoted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RawText {
+    Script,
+    Style,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Data,
+    TagOpen,
+    EndTagOpen,
+    TagName,
+    BeforeAttributeName,
+    AttributeName,
+    AfterAttributeName,
+    BeforeAttributeValue,
+    AttributeValue(Quote),
+    Comment,
+    Bogus,
+    RawText(RawText),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Js {
+    Code,
+    String(char),
+    Escape(char),
+    LineComment,
+    BlockComment,
+}
+
+/// Follows the static text of a template through a small HTML tokenizer,
+/// so that every expression can be escaped for the place it is written into.
+#[derive(Debug, Clone)]
+pub struct HtmlContext {
+    state: State,
+    tag_name: String,
+    closing: bool,
+    attribute_name: String,
+    attribute_value_empty: bool,
+    js: Js,
+    tail: String,
+}
+
+impl HtmlContext {
+    pub fn new() -> Self {
+        HtmlContext {
+            state: State::Data,
+            tag_name: String::new(),
+            closing: false,
+            attribute_name: String::new(),
+            attribute_value_empty: true,
+            js: Js::Code,
+            tail: String::new(),
+        }
+    }
+
+    /// Whether the position is in html text, outside of tags, comments, scripts and styles, where a template starts.
+    pub fn is_text(&self) -> bool {
+        self.state == State::Data
+    }
+
+    /// Whether both are at the same place in the html, like in the value of the same attribute, so that what is written
+    /// at one is escaped the same way at the other. Whether the attribute value is still empty doesn't matter.
+    pub fn is_same_place(&self, other: &HtmlContext) -> bool {
+        match (self.state, other.state) {
+            (State::Data, State::Data) => true,
+            _ => {
+                self.state == other.state
+                    && self.tag_name == other.tag_name
+                    && self.closing == other.closing
+                    && self.attribute_name == other.attribute_name
+                    && self.js == other.js
+            }
+        }
+    }
+
+    pub fn feed(&mut self, text: &str) {
+        for c in text.chars() {
+            self.feed_char(c);
+        }
+    }
+
+    /// Returns the escaping context at the current position and moves past an expression written there.
+    pub fn expression(&mut self) -> Context {
+        let context = self.context();
+
+        match self.state {
+            State::TagOpen => {
+                self.state = State::TagName;
+                self.closing = false;
+                self.tag_name.clear();
+            }
+            State::BeforeAttributeName | State::AfterAttributeName => {
+                self.state = State::AttributeName;
+                self.attribute_name.clear();
+                self.attribute_value_empty = true;
+            }
+            State::BeforeAttributeValue => {
+                self.state = State::AttributeValue(Quote::Unquoted);
+                self.attribute_value_empty = false;
+            }
+            State::AttributeValue(_) => self.attribute_value_empty = false,
+            _ => {}
+        }
+
+        context
+    }
+
+    fn context(&self) -> Context {
+        match self.state {
+            State::Data | State::Comment | State::Bogus | State::EndTagOpen => Context::Html,
+            State::TagOpen | State::TagName | State::BeforeAttributeName | State::AttributeName | State::AfterAttributeName => {
+                Context::UnquotedAttribute
+            }
+            State::BeforeAttributeValue => self.attribute_value_context(Quote::Unquoted),
+            State::AttributeValue(quote) => self.attribute_value_context(quote),
+            State::RawText(RawText::Style) => Context::Css,
+            State::RawText(RawText::Script) => match self.js {
+                Js::String(_) | Js::Escape(_) => Context::JsString,
+                _ => Context::Script,
+            },
+        }
+    }
+
+    fn attribute_value_context(&self, quote: Quote) -> Context {
+        let name = self.attribute_name.as_str();
+
+        if URL_ATTRIBUTES.contains(&name) {
+            if self.attribute_value_empty { Context::Url } else { Context::UrlPart }
+        } else if quote == Quote::Unquoted {
+            Context::UnquotedAttribute
+        } else if name.starts_with("on") {
+            Context::JsString
+        } else if name == "style" {
+            Context::Css
+        } else {
+            Context::Attribute
+        }
+    }
+
+    fn feed_char(&mut self, c: char) {
+        self.tail.push(c.to_ascii_lowercase());
+        if self.tail.len() > 16 {
+            let cut = self.tail.len() - 9;
+            let cut = (cut..self.tail.len()).find(|i| self.tail.is_char_boundary(*i)).unwrap_or(cut);
+            self.tail.drain(..cut);
+        }
+
+        match self.state {
+            State::Data => {
+                if c == '<' {
+                    self.state = State::TagOpen;
+                }
+            }
+            State::TagOpen => match c {
+                '!' => self.state = State::Bogus,
+                '/' => self.state = State::EndTagOpen,
+                c if c.is_ascii_alphabetic() => self.start_tag_name(c, false),
+                '<' => {}
+                _ => self.state = State::Data,
+            },
+            State::EndTagOpen => match c {
+                c if c.is_ascii_alphabetic() => self.start_tag_name(c, true),
+                _ => self.state = State::Bogus,
+            },
+            State::TagName => match c {
+                '>' => self.end_tag(),
+                '/' => self.state = State::BeforeAttributeName,
+                c if c.is_whitespace() => self.state = State::BeforeAttributeName,
+                c => self.tag_name.push(c.to_ascii_lowercase()),
+            },
+            State::BeforeAttributeName => match c {
+                '>' => self.end_tag(),
+                '/' => {}
+                c if c.is_whitespace() => {}
+                c => self.start_attribute_name(c),
+            },
+            State::AttributeName => match c {
+                '>' => self.end_tag(),
+                '=' => self.state = State::BeforeAttributeValue,
+                '/' => self.state = State::BeforeAttributeName,
+                c if c.is_whitespace() => self.state = State::AfterAttributeName,
+                c => self.attribute_name.push(c.to_ascii_lowercase()),
+            },
+            State::AfterAttributeName => match c {
+                '>' => self.end_tag(),
+                '=' => self.state = State::BeforeAttributeValue,
+                '/' => self.state = State::BeforeAttributeName,
+                c if c.is_whitespace() => {}
+                c => self.start_attribute_name(c),
+            },
+            State::BeforeAttributeValue => match c {
+                '>' => self.end_tag(),
+                '"' => self.state = State::AttributeValue(Quote::Double),
+                '\'' => self.state = State::AttributeValue(Quote::Single),
+                c if c.is_whitespace() => {}
+                _ => {
+                    self.state = State::AttributeValue(Quote::Unquoted);
+                    self.attribute_value_empty = false;
+                }
+            },
+            State::AttributeValue(quote) => match (quote, c) {
+                (Quote::Double, '"') | (Quote::Single, '\'') => self.state = State::BeforeAttributeName,
+                (Quote::Unquoted, '>') => self.end_tag(),
+                (Quote::Unquoted, c) if c.is_whitespace() => self.state = State::BeforeAttributeName,
+                _ => self.attribute_value_empty = false,
+            },
+            State::Comment => {
+                if self.tail.ends_with("-->") {
+                    self.state = State::Data;
+                }
+            }
+            State::Bogus => {
+                if self.tail.ends_with("<!--") {
+                    self.state = State::Comment;
+                } else if c == '>' {
+                    self.state = State::Data;
+                }
+            }
+            State::RawText(raw_text) => {
+                if raw_text == RawText::Script {
+                    self.feed_js(c);
+                }
+
+                let end_tag = match raw_text {
+                    RawText::Script => "</script",
+                    RawText::Style => "</style",
+                };
+
+                if self.tail.ends_with(end_tag) {
+                    self.state = State::TagName;
+                    self.closing = true;
+                    self.tag_name = end_tag[2..].to_string();
+                }
+            }
+        }
+    }
+
+    fn feed_js(&mut self, c: char) {
+        let previous = self.tail.chars().rev().nth(1);
+
+        self.js = match (self.js, c) {
+            (Js::Code, '"' | '\'' | '`') => Js::String(c),
+            (Js::Code, '/') if previous == Some('/') => Js::LineComment,
+            (Js::Code, '*') if previous == Some('/') => Js::BlockComment,
+            (Js::String(quote), '\\') => Js::Escape(quote),
+            (Js::String(quote), c) if c == quote => Js::Code,
+            (Js::Escape(quote), _) => Js::String(quote),
+            (Js::LineComment, '\n') => Js::Code,
+            (Js::BlockComment, '/') if previous == Some('*') => Js::Code,
+            (js, _) => js,
+        };
+    }
+
+    fn start_tag_name(&mut self, c: char, closing: bool) {
+        self.state = State::TagName;
+        self.closing = closing;
+        self.tag_name.clear();
+        self.tag_name.push(c.to_ascii_lowercase());
+    }
+
+    fn start_attribute_name(&mut self, c: char) {
+        self.state = State::AttributeName;
+        self.attribute_name.clear();
+        self.attribute_name.push(c.to_ascii_lowercase());
+        self.attribute_value_empty = true;
+    }
+
+    fn end_tag(&mut self) {
+        self.state = match (self.closing, self.tag_name.as_str()) {
+            (false, "script") => {
+                self.js = Js::Code;
+                State::RawText(RawText::Script)
+            }
+            (false, "style") => State::RawText(RawText::Style),
+            _ => State::Data,
+        };
+    }
+}
@@ -1,14 +1,14 @@
 use crate::Node;
 use crate::compiler::Compiler;
-use anyhow::{Result, anyhow};
+use crate::node::Position;
+use anyhow::Result;
 use proc_macro2::TokenStream;
 use quote::quote;
-use std::str::FromStr;
 
 pub struct MatchExprCompiler;
 
 impl MatchExprCompiler {
-    pub fn compile(compiler: &mut Compiler, name: &str, arms: &Vec<(String, Vec<Node>)>) -> Result<TokenStream> {
+    pub fn compile(compiler: &mut Compiler, name: &str, arms: &Vec<(String, Vec<Node>)>, position: &Position) -> Result<TokenStream> {
         let mut arms_ts = TokenStream::new();
 
         for (arm_name, arm_nodes) in arms {
@@ -17,7 +17,8 @@ impl MatchExprCompiler {
                 let ts = compiler.compile(node)?;
                 token_stream.extend(quote! {#ts});
             }
-            let arm_head = TokenStream::from_str(arm_name).map_err(|err| anyhow!("Lex Error: {}", err))?;
+            let arm_head = compiler.rust_code(arm_name, position)?;
+            compiler.validate::<syn::Arm>(quote! { #arm_head => {} }, arm_name, "match arm", position)?;
             let arm_ts = quote! {
                 #arm_head =>  { #token_stream },
             };
@@ -25,9 +26,18 @@ impl MatchExprCompiler {
             arms_ts.extend(arm_ts);
         }
 
-        let name_head = TokenStream::from_str(name).map_err(|err| anyhow!("Lex Error: {}", err))?;
+        let name_head = compiler.rust_code(name, position)?;
+        compiler.validate::<syn::Expr>(quote! { #name_head {} }, name, "match expression", position)?;
+        let (definition_ts, name_head) = match syn::parse2::<syn::ExprMatch>(quote! { #name_head {} }) {
+            Ok(syn::ExprMatch { expr, .. }) => {
+                let (definition_ts, expr_ts) = compiler.at_position(quote! { #expr }, position, "");
+                (definition_ts, quote! { match #expr_ts })
+            }
+            Err(_) => (TokenStream::new(), name_head),
+        };
 
         Ok(quote! {
+           #definition_ts
            #name_head {
              #arms_ts
            }
@@ -1,21 +1,40 @@
 use crate::Node;
 use crate::compiler::Compiler;
-use crate::node::{RustBlockContent, TextBlockItem, TextLineItem};
-use anyhow::{Result, anyhow};
+use crate::node::{Position, RustBlockContent, TextBlockItem, TextLineItem};
+use anyhow::Result;
 use proc_macro2::TokenStream;
 use quote::quote;
-use std::str::FromStr;
 
 pub struct RustBlockCompiler;
 
 impl RustBlockCompiler {
-    pub fn compile(compiler: &mut Compiler, contents: &Vec<RustBlockContent>) -> Result<TokenStream> {
+    pub fn compile(compiler: &mut Compiler, contents: &Vec<RustBlockContent>, position: &Position) -> Result<TokenStream> {
+        let contents_ts = Self::compile_contents(compiler, contents, position)?;
+        compiler.validate::<syn::Block>(quote! { { #contents_ts } }, &Self::code(contents), "code block", position)?;
+
+        Ok(contents_ts)
+    }
+
+    /// The Rust code of the block without its text lines, for error messages.
+    fn code(contents: &[RustBlockContent]) -> String {
+        contents
+            .iter()
+            .map(|content| match content {
+                RustBlockContent::Code(code) => code.clone(),
+                RustBlockContent::NestedBlock(nested_contents) => format!("{{ {} }}", Self::code(nested_contents)),
+                _ => String::new(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn compile_contents(compiler: &mut Compiler, contents: &Vec<RustBlockContent>, position: &Position) -> Result<TokenStream> {
         let mut token_stream = TokenStream::new();
 
         for content in contents {
             match content {
                 RustBlockContent::Code(code) => {
-                    let code_ts = TokenStream::from_str(code).map_err(|err| anyhow!("Lex Error: {}", err))?;
+                    let code_ts = compiler.rust_code(code, position)?;
                     token_stream.extend(quote! { #code_ts });
                 }
                 RustBlockContent::TextLine(items) => {
@@ -26,7 +45,7 @@ impl RustBlockCompiler {
                                 token_stream.extend(quote! {#t_ts});
                             }
                             TextLineItem::RustExprSimple(expr, is_escaped) => {
-                                let rxs_ts = compiler.compile(&Node::RustExprSimple(expr.clone(), *is_escaped))?;
+                                let rxs_ts = compiler.compile(&Node::RustExprSimple(expr.clone(), *is_escaped, position.clone()))?;
                                 token_stream.extend(quote! {#rxs_ts});
                             }
                         }
@@ -40,14 +59,14 @@ impl RustBlockCompiler {
                                 token_stream.extend(quote! {#t_ts});
                             }
                             TextBlockItem::RustExprSimple(expr, is_escaped) => {
-                                let rxs_ts = compiler.compile(&Node::RustExprSimple(expr.clone(), *is_escaped))?;
+                                let rxs_ts = compiler.compile(&Node::RustExprSimple(expr.clone(), *is_escaped, position.clone()))?;
                                 token_stream.extend(quote! {#rxs_ts});
                             }
                         }
                     }
                 }
                 RustBlockContent::NestedBlock(nested_contents) => {
-                    let nested_ts = Self::compile(compiler, nested_contents)?;
+                    let nested_ts = Self::compile_contents(compiler, nested_contents, position)?;
                     token_stream.extend(quote! { {#nested_ts} });
                 }
             }
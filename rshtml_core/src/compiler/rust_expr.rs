@@ -1,28 +1,108 @@
 use crate::Node;
 use crate::compiler::Compiler;
-use anyhow::{Result, anyhow};
-use proc_macro2::TokenStream;
+use crate::node::Position;
+use anyhow::Result;
+use proc_macro2::{TokenStream, TokenTree};
 use quote::quote;
-use std::str::FromStr;
+use syn::{Expr, ExprForLoop, ExprIf, ExprLet, ExprWhile};
 
 pub struct RustExprCompiler;
 
 impl RustExprCompiler {
-    pub fn compile(compiler: &mut Compiler, exprs: &Vec<(String, Vec<Node>)>) -> Result<TokenStream> {
+    pub fn compile(compiler: &mut Compiler, exprs: &[(String, Vec<Node>)], position: &Position) -> Result<TokenStream> {
         let mut ts = TokenStream::new();
+        let mut definitions_ts = TokenStream::new();
 
-        for (expr, inner_nodes) in exprs {
+        for (i, (expr, inner_nodes)) in exprs.iter().enumerate() {
             let mut inner_ts = TokenStream::new();
             for inner_node in inner_nodes {
                 let its = compiler.compile(inner_node)?;
                 inner_ts.extend(quote! {#its});
             }
 
-            let expr_code = TokenStream::from_str(expr).map_err(|err| anyhow!("Lex Error: {}", err))?;
+            let expr_code = compiler.rust_code(expr, position)?;
+            let clause_ts = if i == 0 {
+                quote! { #expr_code {} }
+            } else {
+                quote! { if true {} #expr_code {} }
+            };
+            compiler.validate::<syn::Expr>(clause_ts.clone(), expr, "expression", position)?;
+            let expr_code = Self::head_at_position(compiler, clause_ts, i, position, &mut definitions_ts).unwrap_or(expr_code);
 
             ts.extend(quote! { #expr_code { #inner_ts } });
         }
 
-        Ok(ts)
+        Ok(quote! { #definitions_ts #ts })
+    }
+
+    /// The head of clause `i`, like `if cond`, `else if cond` or `for pat in expr`, with its condition or the value it
+    /// loops over wrapped by [`Compiler::at_position`], or `None` for a head that is kept as it is. `clause_ts` is the
+    /// clause as it was validated.
+    fn head_at_position(
+        compiler: &Compiler,
+        clause_ts: TokenStream,
+        i: usize,
+        position: &Position,
+        definitions_ts: &mut TokenStream,
+    ) -> Option<TokenStream> {
+        let clause = syn::parse2::<Expr>(clause_ts).ok()?;
+        let clause = match (i, clause) {
+            (0, clause) => clause,
+            (
+                _,
+                Expr::If(ExprIf {
+                    else_branch: Some((_, clause)),
+                    ..
+                }),
+            ) => *clause,
+            _ => return None,
+        };
+
+        // the clauses of an `if` share its position
+        let part = if i == 0 { String::new() } else { i.to_string() };
+        let mut at_position = |expr: &Expr| {
+            let (definition_ts, expr_ts) = compiler.at_position(quote! { #expr }, position, &part);
+            definitions_ts.extend(definition_ts);
+            expr_ts
+        };
+        let else_ts = if i == 0 {
+            quote! {}
+        } else {
+            quote! { else }
+        };
+
+        match clause {
+            Expr::If(ExprIf { cond, .. }) => {
+                let cond_ts = Self::condition_at_position(&cond, at_position)?;
+                Some(quote! { #else_ts if #cond_ts })
+            }
+            Expr::While(ExprWhile { label, cond, .. }) => {
+                let cond_ts = Self::condition_at_position(&cond, at_position)?;
+                Some(quote! { #label while #cond_ts })
+            }
+            Expr::ForLoop(ExprForLoop { label, pat, expr, .. }) => {
+                let expr_ts = at_position(&expr);
+                Some(quote! { #label for #pat in #expr_ts })
+            }
+            _ => None,
+        }
+    }
+
+    /// The condition of an `if` or `while`, or the value of its `let`. A chain of `let`s and conditions is kept as it is.
+    fn condition_at_position(cond: &Expr, mut at_position: impl FnMut(&Expr) -> TokenStream) -> Option<TokenStream> {
+        match cond {
+            Expr::Let(ExprLet { pat, expr, .. }) => {
+                let expr_ts = at_position(expr);
+                Some(quote! { let #pat = #expr_ts })
+            }
+            Expr::Binary(_)
+                if quote! { #cond }
+                    .into_iter()
+                    .any(|tt| matches!(tt, TokenTree::Ident(ident) if ident == "let")) =>
+            {
+                None
+            }
+            cond => Some(at_position(cond)),
+        }
     }
 }
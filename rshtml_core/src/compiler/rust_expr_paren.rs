@@ -1,14 +1,24 @@
 use crate::compiler::Compiler;
-use anyhow::{Result, anyhow};
+use crate::node::Position;
+use crate::optimizer::literal_value;
+use anyhow::Result;
 use proc_macro2::TokenStream;
-use std::str::FromStr;
+use quote::quote;
 
 pub struct RustExprParenCompiler;
 
 impl RustExprParenCompiler {
-    pub fn compile(compiler: &mut Compiler, expr: &str, is_escaped: &bool) -> Result<TokenStream> {
-        let expr_ts = TokenStream::from_str(expr).map_err(|err| anyhow!("Lex Error: {}", err))?;
+    pub fn compile(compiler: &mut Compiler, expr: &str, is_escaped: &bool, position: &Position) -> Result<TokenStream> {
+        if let Some(value) = literal_value(expr) {
+            return compiler.escape_or_raw_literal(&value, is_escaped);
+        }
 
-        Ok(compiler.escape_or_raw(expr_ts, is_escaped))
+        let expr_ts = compiler.rust_code(expr, position)?;
+        compiler.validate::<syn::Expr>(expr_ts.clone(), expr, "expression", position)?;
+
+        let (definition_ts, expr_ts) = compiler.at_position(expr_ts, position, "");
+        let render_ts = compiler.escape_or_raw(expr_ts, is_escaped);
+
+        Ok(quote! { #definition_ts #render_ts })
     }
 }
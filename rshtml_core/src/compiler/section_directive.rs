@@ -11,7 +11,9 @@ impl SectionDirectiveCompiler {
     pub fn compile(compiler: &mut Compiler, name: &str, content: &SectionDirectiveContent) -> Result<TokenStream> {
         let content_ts = match content {
             SectionDirectiveContent::Text(text) => compiler.compile(&Node::Text(text.clone()))?,
-            SectionDirectiveContent::RustExprSimple(expr, is_escaped) => compiler.compile(&Node::RustExprSimple(expr.clone(), *is_escaped))?,
+            SectionDirectiveContent::RustExprSimple(expr, is_escaped, position) => {
+                compiler.compile(&Node::RustExprSimple(expr.clone(), *is_escaped, position.clone()))?
+            }
         };
 
         compiler.sections.insert(name.to_owned(), content_ts.clone());
@@ -0,0 +1,26 @@
+use crate::compiler::Compiler;
+use crate::compiler::component::ComponentCompiler;
+use crate::diagnostic::Diagnostic;
+use crate::node::Position;
+use anyhow::Result;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+pub struct SlotDirectiveCompiler;
+
+impl SlotDirectiveCompiler {
+    /// Calls the writer of the slot when the call site gave it, a slot that wasn't given renders nothing.
+    pub fn compile(compiler: &mut Compiler, name: &str, position: &Position) -> Result<TokenStream> {
+        if compiler.content_places.is_empty() {
+            return Err(Diagnostic::new(format!("@slot(\"{}\") can only be used in a component", name), position.clone()).into());
+        }
+        ComponentCompiler::record_content_place(compiler, Some(name), Some(position))?;
+
+        let slot_ident = format_ident!("__slot_{}__", name);
+        Ok(quote! {
+            if let Some(__slot__) = #slot_ident {
+                __slot__(__f__)?;
+            }
+        })
+    }
+}
@@ -8,9 +8,12 @@ use std::path::Path;
 pub struct UseDirectiveCompiler;
 
 impl UseDirectiveCompiler {
-    pub fn compile(compiler: &mut Compiler, name: &String, path: &Path, component: &Node) -> Result<TokenStream> {
+    pub fn compile(compiler: &mut Compiler, name: &String, path: &Path, component: &Node, inline: bool) -> Result<TokenStream> {
         compiler.use_directives.push((name.to_string(), path.to_path_buf()));
         compiler.components.insert(name.to_string(), (*component).clone());
+        if inline {
+            compiler.inline_components.insert(name.to_string());
+        }
 
         Ok(quote! {})
     }
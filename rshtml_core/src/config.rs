@@ -51,18 +51,15 @@ impl Config {
 
         if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
             let cargo_toml_path = Path::new(&manifest_dir).join("Cargo.toml");
-            if let Ok(content) = std::fs::read_to_string(cargo_toml_path) {
-                if let Ok(manifest) = toml::from_str::<Manifest>(&content) {
-                    if let Some(pkg) = manifest.package {
-                        if let Some(metadata) = pkg.metadata {
-                            if let Some(toml_config) = metadata.rshtml {
-                                if let Some(views) = toml_config.views {
-                                    config.set_views((views.path, views.layout));
-                                }
-                            }
-                        }
-                    }
-                }
+            if let Ok(content) = std::fs::read_to_string(cargo_toml_path)
+                && let Ok(manifest) = toml::from_str::<Manifest>(&content)
+                && let Some(views) = manifest
+                    .package
+                    .and_then(|pkg| pkg.metadata)
+                    .and_then(|metadata| metadata.rshtml)
+                    .and_then(|toml_config| toml_config.views)
+            {
+                config.set_views((views.path, views.layout));
             }
         }
 
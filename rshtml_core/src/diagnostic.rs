@@ -0,0 +1,140 @@
+use crate::error::ParseError;
+use crate::node::Position;
+use std::collections::HashMap;
+use std::fmt;
+
+/// An error in a template, reported with its position, the offending line and the templates that included the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    /// Line and column are 0 when the error is about the whole file, e.g. when it can't be read.
+    pub position: Position,
+    /// Columns underlined in the code frame, at least 1.
+    pub length: usize,
+    pub source_line: Option<String>,
+    /// The `@include`, `@use` and `@extends` directives that led to the file, innermost first.
+    pub included_from: Vec<Position>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>, position: Position) -> Self {
+        Diagnostic {
+            message: message.into(),
+            position,
+            length: 1,
+            source_line: None,
+            included_from: Vec::new(),
+        }
+    }
+
+    pub(crate) fn from_parse_error(err: ParseError, file: &str) -> Self {
+        let position = Position {
+            file: file.to_string(),
+            line: err.line,
+            column: err.column,
+        };
+
+        Diagnostic {
+            length: err.length,
+            ..Diagnostic::new(err.message, position)
+        }
+    }
+}
+
+impl From<anyhow::Error> for Diagnostic {
+    fn from(err: anyhow::Error) -> Self {
+        err.downcast::<Diagnostic>()
+            .unwrap_or_else(|err| Diagnostic::new(err.to_string(), Position::default()))
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "error: {}", self.message)?;
+
+        let Position { file, line, column } = &self.position;
+        let gutter = " ".repeat(line.to_string().len());
+
+        match (*line, &self.source_line) {
+            (0, _) if file.is_empty() => {}
+            (0, _) => writeln!(f, "{}--> {}", gutter, file)?,
+            (_, None) => writeln!(f, "{}--> {}", gutter, self.position)?,
+            (_, Some(source_line)) => {
+                let source_line = source_line.trim_end();
+                let indent: String = source_line
+                    .chars()
+                    .take(column.saturating_sub(1))
+                    .map(|c| if c == '\t' { c } else { ' ' })
+                    .collect();
+
+                writeln!(f, "{}--> {}", gutter, self.position)?;
+                writeln!(f, "{} |", gutter)?;
+                writeln!(f, "{} | {}", line, source_line)?;
+                writeln!(f, "{} | {}{}", gutter, indent, "^".repeat(self.length))?;
+            }
+        }
+
+        for position in &self.included_from {
+            writeln!(f, "{} = note: included from {}", gutter, position)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// All the errors found in a template and the templates it uses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl std::error::Error for Diagnostics {}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, diagnostic) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", diagnostic)?;
+        }
+
+        if self.0.len() > 1 {
+            write!(f, "\n{} template errors", self.0.len())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The source of every template file read while parsing, with the directives that included it.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: HashMap<String, (String, Vec<Position>)>,
+}
+
+impl SourceMap {
+    pub fn insert(&mut self, file: &str, source: &str, included_from: &[Position]) {
+        self.files
+            .entry(file.to_string())
+            .or_insert_with(|| (source.to_string(), included_from.iter().rev().cloned().collect()));
+    }
+
+    /// Fills in the offending line and the include chain of diagnostics that only know their position.
+    pub fn annotate(&self, diagnostics: &mut [Diagnostic]) {
+        for diagnostic in diagnostics {
+            let Some((source, included_from)) = self.files.get(&diagnostic.position.file) else {
+                continue;
+            };
+
+            if diagnostic.source_line.is_none() && diagnostic.position.line > 0 {
+                // an error at the end of the input is on the line after the last one
+                diagnostic.source_line = Some(source.lines().nth(diagnostic.position.line - 1).unwrap_or_default().to_string());
+            }
+
+            if diagnostic.included_from.is_empty() {
+                diagnostic.included_from = included_from.clone();
+            }
+        }
+    }
+}
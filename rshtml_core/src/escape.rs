@@ -0,0 +1,225 @@
+use std::fmt;
+use std::fmt::{Display, Write};
+
+/// How an expression's output is escaped, decided by where in the HTML document it is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    /// Written as is, selected with the `@#` prefix.
+    Raw,
+    /// Element text content.
+    Html,
+    /// A quoted attribute value.
+    Attribute,
+    /// An unquoted attribute value, or any other position inside a tag.
+    UnquotedAttribute,
+    /// The start of a URL-valued attribute such as `href` or `src`.
+    Url,
+    /// A later part of a URL-valued attribute, e.g. a path segment or query parameter.
+    UrlPart,
+    /// Code inside a `<script>` element, outside of any string literal. Values are written as JavaScript strings.
+    Script,
+    /// Code inside a `<script>` element written with `@#`, as is but for `</script`, `<!--` and U+2028/U+2029.
+    RawScript,
+    /// A JavaScript string literal, or an `on*` event handler attribute.
+    JsString,
+    /// A `<style>` element or a `style` attribute.
+    Css,
+}
+
+const SAFE_URL_SCHEMES: [&str; 4] = ["http", "https", "mailto", "tel"];
+const UNSAFE_URL: &str = "about:invalid#rshtml-unsafe-url";
+
+/// A `fmt::Write` adapter that escapes everything written through it for the given [`Context`].
+///
+/// Runs of characters that need no escaping are forwarded to the inner writer as whole slices,
+/// so a `Display` value can be formatted straight into the adapter without an intermediate `String`.
+/// The `Url`, `Script` and `RawScript` contexts have to look at more than one chunk, call [`Escaper::finish`] after writing.
+pub struct Escaper<'a, W: Write + ?Sized = dyn Write> {
+    inner: &'a mut W,
+    context: Context,
+    pending: String,
+    url_state: UrlState,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum UrlState {
+    Scheme,
+    Allowed,
+    Blocked,
+}
+
+impl<'a, W: Write + ?Sized> Escaper<'a, W> {
+    pub fn new(inner: &'a mut W) -> Self {
+        Self::with_context(inner, Context::Html)
+    }
+
+    pub fn with_context(inner: &'a mut W, context: Context) -> Self {
+        Escaper {
+            inner,
+            context,
+            pending: String::new(),
+            url_state: UrlState::Scheme,
+        }
+    }
+
+    pub fn finish(mut self) -> fmt::Result {
+        match self.context {
+            Context::Url if self.url_state == UrlState::Scheme => {
+                let pending = std::mem::take(&mut self.pending);
+                escape_str(self.inner, Context::Url, &pending)
+            }
+            Context::Script => {
+                // a string is a string, whatever it contains; JSON is written with `@json(...)`
+                let quoted = serde_json::to_string(&self.pending).map_err(|_| fmt::Error)?;
+                escape_str(self.inner, Context::Script, &quoted)
+            }
+            Context::RawScript => {
+                let pending = std::mem::take(&mut self.pending);
+                write_raw_script(self.inner, &pending)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn write_url(&mut self, s: &str) -> fmt::Result {
+        match self.url_state {
+            UrlState::Allowed => escape_str(self.inner, Context::Url, s),
+            UrlState::Blocked => Ok(()),
+            UrlState::Scheme => {
+                let Some(end) = s.find([':', '/', '?', '#']) else {
+                    self.pending.push_str(s);
+                    return Ok(());
+                };
+
+                self.pending.push_str(&s[..end]);
+                let pending = std::mem::take(&mut self.pending);
+
+                if s[end..].starts_with(':') {
+                    let scheme: String = pending
+                        .chars()
+                        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+                        .collect::<String>()
+                        .to_ascii_lowercase();
+
+                    if !SAFE_URL_SCHEMES.contains(&scheme.as_str()) {
+                        self.url_state = UrlState::Blocked;
+                        return self.inner.write_str(UNSAFE_URL);
+                    }
+                }
+
+                self.url_state = UrlState::Allowed;
+                escape_str(self.inner, Context::Url, &pending)?;
+                escape_str(self.inner, Context::Url, &s[end..])
+            }
+        }
+    }
+}
+
+impl<W: Write + ?Sized> Write for Escaper<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match self.context {
+            Context::Raw => self.inner.write_str(s),
+            Context::Url => self.write_url(s),
+            Context::Script | Context::RawScript => {
+                self.pending.push_str(s);
+                Ok(())
+            }
+            context => escape_str(self.inner, context, s),
+        }
+    }
+}
+
+/// Formats `value` into `f`, escaped for `context`.
+pub fn escape<W: Write + ?Sized, T: Display + ?Sized>(f: &mut W, context: Context, value: &T) -> fmt::Result {
+    let mut escaper = Escaper::with_context(f, context);
+    write!(escaper, "{}", value)?;
+    escaper.finish()
+}
+
+/// Writes JSON into a `<script>` element, with the characters that could end the element or the string escaped.
+pub(crate) fn write_script_json<W: Write + ?Sized>(f: &mut W, json: &str) -> fmt::Result {
+    escape_str(f, Context::Script, json)
+}
+
+/// Writes trusted code into a `<script>` element, breaking up what the HTML parser would read as its end.
+fn write_raw_script<W: Write + ?Sized>(f: &mut W, s: &str) -> fmt::Result {
+    let mut last = 0;
+
+    for (i, c) in s.char_indices() {
+        let rest = &s[i..];
+        let escaped = match c {
+            '<' if rest.get(..8).is_some_and(|tag| tag.eq_ignore_ascii_case("</script")) => "<\\/",
+            '<' if rest.starts_with("<!--") => "<\\!",
+            '\u{2028}' => "\\u2028",
+            '\u{2029}' => "\\u2029",
+            _ => continue,
+        };
+
+        f.write_str(&s[last..i])?;
+        f.write_str(escaped)?;
+        last = i + if c == '<' { 2 } else { c.len_utf8() };
+    }
+
+    f.write_str(&s[last..])
+}
+
+fn escape_str<W: Write + ?Sized>(f: &mut W, context: Context, s: &str) -> fmt::Result {
+    let mut last = 0;
+
+    for (i, c) in s.char_indices() {
+        if !needs_escape(context, c) {
+            continue;
+        }
+
+        if last < i {
+            f.write_str(&s[last..i])?;
+        }
+        write_escaped(f, context, c)?;
+        last = i + c.len_utf8();
+    }
+
+    if last < s.len() {
+        f.write_str(&s[last..])?;
+    }
+
+    Ok(())
+}
+
+fn needs_escape(context: Context, c: char) -> bool {
+    match context {
+        Context::Raw | Context::RawScript => false,
+        Context::Html => matches!(c, '&' | '<' | '>' | '"' | '\'' | '/'),
+        Context::Attribute => matches!(c, '&' | '<' | '>' | '"' | '\'' | '`'),
+        Context::UnquotedAttribute => matches!(c, '&' | '<' | '>' | '"' | '\'' | '`' | '=') || c.is_ascii_whitespace(),
+        Context::Url => !(c.is_ascii_alphanumeric() || "-._~:/?#[]@!$()*+,;=%".contains(c)),
+        Context::UrlPart => !(c.is_ascii_alphanumeric() || "-._~".contains(c)),
+        Context::Script => matches!(c, '<' | '>' | '&' | '\u{2028}' | '\u{2029}'),
+        Context::JsString => (c.is_ascii() && !(c.is_ascii_alphanumeric() || ",._-".contains(c))) || matches!(c, '\u{2028}' | '\u{2029}'),
+        Context::Css => c.is_ascii() && !(c.is_ascii_alphanumeric() || " #.%-_,".contains(c)),
+    }
+}
+
+fn write_escaped<W: Write + ?Sized>(f: &mut W, context: Context, c: char) -> fmt::Result {
+    match context {
+        Context::Raw | Context::RawScript => f.write_char(c),
+        Context::Html | Context::Attribute | Context::UnquotedAttribute => match c {
+            '&' => f.write_str("&amp;"),
+            '<' => f.write_str("&lt;"),
+            '>' => f.write_str("&gt;"),
+            '"' => f.write_str("&quot;"),
+            '\'' => f.write_str("&#39;"),
+            '/' => f.write_str("&#x2F;"),
+            c => write!(f, "&#{};", c as u32),
+        },
+        Context::Url if c == '&' => f.write_str("&amp;"),
+        Context::Url | Context::UrlPart => {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                write!(f, "%{:02X}", byte)?;
+            }
+            Ok(())
+        }
+        Context::Script | Context::JsString => write!(f, "\\u{:04X}", c as u32),
+        Context::Css => write!(f, "\\{:x} ", c as u32),
+    }
+}
@@ -1,21 +1,53 @@
 mod time;
 
+use crate::escape::{Context, escape, write_script_json};
+use crate::markup::Markup;
+use crate::traits::Render;
 use serde::Serialize;
+use std::fmt;
+use std::fmt::Write;
 use std::string::ToString;
 pub use time::*;
 
-pub fn json<T: Serialize>(value: &T) -> String {
-    serde_json::to_string(value).unwrap_or_else(|err| {
+/// Serializes `value` for `@json(...)`, which writes it as JSON in a `<script>` element and as escaped text elsewhere.
+pub fn json<T: Serialize>(value: &T) -> Json {
+    Json(serde_json::to_string(value).unwrap_or_else(|err| {
         eprintln!("DEBUG: JSON error: {}", err);
         "{}".to_string()
-    })
+    }))
 }
 
-pub fn json_let<T: Serialize>(name: &str, value: &T) -> String {
+/// A serialized value, returned by [`json`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Json(pub String);
+
+impl Json {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Render for Json {
+    fn render_to<W: Write + ?Sized>(&self, f: &mut W, context: Context) -> fmt::Result {
+        match context {
+            Context::Script | Context::RawScript => write_script_json(f, &self.0),
+            context => escape(f, context, &self.0),
+        }
+    }
+}
+
+pub fn json_let<T: Serialize>(name: &str, value: &T) -> Markup {
     let json = serde_json::to_string(value).unwrap_or_else(|err| {
         eprintln!("DEBUG: JSON error: {}", err);
         "{}".to_string()
     });
 
-    format!("let {} = {}", name, json)
+    let json = json
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026")
+        .replace('\u{2028}', "\\u2028")
+        .replace('\u{2029}', "\\u2029");
+
+    Markup(format!("let {} = {}", name, json))
 }
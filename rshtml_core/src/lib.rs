@@ -2,87 +2,264 @@
 
 mod compiler;
 pub mod config;
+pub mod diagnostic;
 mod error;
+pub mod escape;
 pub mod functions;
+pub mod markup;
 mod node;
+mod optimizer;
 mod parser;
+#[cfg(feature = "stream")]
+pub mod stream;
+pub mod template_fn;
 #[cfg(test)]
 mod tests;
 pub mod traits;
+pub mod writer;
 
 use crate::config::Config;
-use crate::parser::RsHtmlParser;
-use anyhow::Result;
-use node::Node;
+use crate::diagnostic::{Diagnostic, Diagnostics};
+use crate::optimizer::Optimizer;
+
+/// The template tree, for tools like editors that parse templates without compiling them.
+pub use crate::node::{
+    ComponentParameter, ComponentParameterValue, ComponentProp, Node, Position, RustBlockContent, SectionDirectiveContent, TextBlockItem,
+    TextLineItem,
+};
+/// Parses templates, with [`RsHtmlParser::run_partial`] returning the partial tree of a half-written one along with its errors.
+pub use crate::parser::RsHtmlParser;
 use proc_macro2::{Ident, TokenStream};
-use quote::{quote, quote_spanned};
+use quote::{format_ident, quote, quote_spanned};
 use std::clone::Clone;
+use syn::{Fields, Generics, LitStr, Variant};
+
+/// Where the source of a template comes from.
+#[derive(Clone)]
+pub enum TemplateSource {
+    /// A file, relative to the views folder.
+    Path(String),
+    /// Source written inline, as in `#[rshtml(source = "...")]` or `rshtml!("...")`. Errors are reported at the literal.
+    Inline(LitStr),
+}
+
+impl From<String> for TemplateSource {
+    fn from(path: String) -> Self {
+        TemplateSource::Path(path)
+    }
+}
+
+/// Generates the `RsHtml` impl of `struct_name`, or the `RsHtmlMut` impl when the template mutates its data.
+///
+/// The struct's generics, lifetimes and where-clause are carried over to the impl.
+pub fn process_template(template: impl Into<TemplateSource>, struct_name: &Ident, generics: &Generics, mutable: bool) -> TokenStream {
+    let (body, text_size) = match compile_template(&template.into(), struct_name) {
+        Ok(compiled) => compiled,
+        Err(err) => return err,
+    };
+
+    generate_impl(struct_name, generics, mutable, body, text_size)
+}
+
+/// Generates the impl of an enum whose variants are rendered with their own templates.
+///
+/// The fields of the matched variant are bound by name, tuple fields as `_0`, `_1`, ...
+pub fn process_enum_template(enum_name: &Ident, generics: &Generics, variants: Vec<(&Variant, TemplateSource)>, mutable: bool) -> TokenStream {
+    let mut arms = TokenStream::new();
+    let mut max_text_size = 0;
+
+    for (variant, template) in variants {
+        let (body, text_size) = match compile_template(&template, enum_name) {
+            Ok(compiled) => compiled,
+            Err(err) => return err,
+        };
+        max_text_size = max_text_size.max(text_size);
+
+        let variant_name = &variant.ident;
+        let pattern = match &variant.fields {
+            Fields::Named(fields) => {
+                let names = fields.named.iter().map(|field| &field.ident);
+                quote! {Self::#variant_name { #(#names),* }}
+            }
+            Fields::Unnamed(fields) => {
+                let names = (0..fields.unnamed.len()).map(|i| format_ident!("_{}", i));
+                quote! {Self::#variant_name ( #(#names),* )}
+            }
+            Fields::Unit => quote! {Self::#variant_name},
+        };
+
+        arms.extend(quote! {
+            #[allow(unused_variables)]
+            #pattern => { #body }
+        });
+    }
+
+    let body = quote! {
+        match self {
+            #arms
+        }
+    };
+
+    generate_impl(enum_name, generics, mutable, body, max_text_size)
+}
+
+/// Compiles the template given to `rshtml!`, returning a value that renders it and borrows the variables it uses.
+pub fn process_inline_template(source: &LitStr) -> TokenStream {
+    let name = Ident::new("rshtml", source.span());
+
+    template_fn(&TemplateSource::Inline(source.clone()), &name, quote! {})
+}
+
+/// Compiles the template of a `#[template("...")]` function, whose arguments are in scope instead of `self`.
+///
+/// Returns the new body of the function, a value that owns the arguments and renders the template.
+pub fn process_function_template(template: impl Into<TemplateSource>, function_name: &Ident) -> TokenStream {
+    template_fn(&template.into(), function_name, quote! {move})
+}
+
+fn template_fn(template: &TemplateSource, name: &Ident, capture: TokenStream) -> TokenStream {
+    let (body, _) = match compile_template(template, name) {
+        Ok(compiled) => compiled,
+        Err(err) => return err,
+    };
+
+    let rs = imports();
 
-pub fn process_template(template_name: String, struct_name: &Ident) -> TokenStream {
+    quote! {{
+        #rs
+
+        rshtml::TemplateFn::new(#capture |__f__: &mut dyn ::std::fmt::Write, __flush__: &dyn Fn() -> ::std::fmt::Result| -> ::std::fmt::Result {
+            #body
+
+            Ok(())
+        })
+    }}
+}
+
+fn compile_template(template: &TemplateSource, name: &Ident) -> std::result::Result<(TokenStream, usize), TokenStream> {
     let config = Config::load_from_toml_or_default();
-    let (_, layout) = config.views.clone();
 
-    let (compiled_ast_tokens, sections, text_size) = match parse_and_compile(&template_name, config) {
+    let (compiled_ast_tokens, sections, text_size) = match parse_and_compile(template, config) {
         Ok(tokens) => tokens,
         Err(err) => {
-            let error_message = format!(
-                "Template processing failed for struct `{}` with template `{}`:\n{}",
-                struct_name, template_name, err
-            );
+            let (template_name, span) = match template {
+                TemplateSource::Path(path) => (format!("template `{}`", path), name.span()),
+                TemplateSource::Inline(source) => ("inline template".to_string(), source.span()),
+            };
+            let error_message = format!("Template processing failed for `{}` with {}:\n{}", name, template_name, err);
 
-            return quote_spanned! { struct_name.span() => compile_error!(#error_message); };
+            return Err(quote_spanned! { span => compile_error!(#error_message); });
         }
     };
 
-    let text_size = text_size + ((text_size as f64 * 0.10) as usize).clamp(32, 512);
-
     //dbg!("DEBUG: Generated write_calls TokenStream:\n{}", compiled_ast_tokens.to_string());
 
-    let rs = quote! {
-        #[allow(non_upper_case_globals)]
-        const layout: &str = #layout;
+    let body = quote! {
+        #[allow(dead_code)]
         fn has_section(section: &str) -> bool {#sections.contains(&section)}
+
+        #compiled_ast_tokens
+    };
+
+    Ok((body, text_size))
+}
+
+fn imports() -> TokenStream {
+    let (_, layout) = Config::load_from_toml_or_default().views;
+
+    quote! {
+        #[allow(non_upper_case_globals, dead_code)]
+        const layout: &str = #layout;
         #[allow(unused_imports)]
         use rshtml::functions::*;
+        #[allow(unused_imports)]
+        use rshtml::traits::{RenderRef as _, RenderValue as _};
+        #[allow(unused_imports)]
+        use ::std::fmt::Write as _;
+    }
+}
+
+fn generate_impl(name: &Ident, generics: &Generics, mutable: bool, body: TokenStream, text_size: usize) -> TokenStream {
+    let text_size = text_size + ((text_size as f64 * 0.10) as usize).clamp(32, 512);
+    let rs = imports();
+
+    let (trait_ts, self_ts) = if mutable {
+        (quote! {rshtml::traits::RsHtmlMut}, quote! {&mut self})
+    } else {
+        (quote! {rshtml::traits::RsHtml}, quote! {&self})
     };
 
-    let generated_code = quote! {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
         const _ : () = {
 
             #rs
 
-            impl rshtml::traits::RsHtml for #struct_name {
-                fn fmt(&mut self, __f__: &mut dyn ::std::fmt::Write) -> ::std::fmt::Result {
+            impl #impl_generics #trait_ts for #name #ty_generics #where_clause {
+                fn fmt(#self_ts, __f__: &mut dyn ::std::fmt::Write) -> ::std::fmt::Result {
+                    #trait_ts::fmt_into(self, __f__, &|| Ok(()))
+                }
+
+                fn fmt_with_flush(
+                    #self_ts,
+                    __f__: &mut dyn ::std::fmt::Write,
+                    __flush__: &dyn Fn() -> ::std::fmt::Result,
+                ) -> ::std::fmt::Result {
+                    #trait_ts::fmt_into(self, __f__, __flush__)
+                }
+
+                fn fmt_into<__W: ::std::fmt::Write + ?Sized>(
+                    #self_ts,
+                    __f__: &mut __W,
+                    __flush__: &dyn Fn() -> ::std::fmt::Result,
+                ) -> ::std::fmt::Result {
 
-                    #compiled_ast_tokens
+                    #body
 
                     Ok(())
                 }
 
-                fn render(&mut self) -> Result<String, ::std::fmt::Error> {
+                fn render(#self_ts) -> Result<String, ::std::fmt::Error> {
                     let mut buf = String::with_capacity(#text_size);
-                    self.fmt(&mut buf)?;
+                    #trait_ts::fmt_into(self, &mut buf, &|| Ok(()))?;
                     Ok(buf)
                 }
             }
         };
-    };
-
-    generated_code
+    }
 }
 
-fn parse_and_compile(template_path: &str, config: Config) -> Result<(TokenStream, TokenStream, usize)> {
+fn parse_and_compile(template: &TemplateSource, config: Config) -> Result<(TokenStream, TokenStream, usize), Diagnostics> {
     let mut rshtml_parser = RsHtmlParser::new();
-    let node = rshtml_parser.run(template_path, config)?;
+    let node = match template {
+        TemplateSource::Path(path) => rshtml_parser.run(path, config)?,
+        TemplateSource::Inline(source) => rshtml_parser.run_source(&source.value(), config)?,
+    };
+    let node = Optimizer::optimize(node);
 
     let mut compiler = compiler::Compiler::new();
-    let ts = compiler.compile(&node)?;
+    let mut ts = compiler.compile(&node).unwrap_or_else(|err| {
+        compiler.diagnostics.push(Diagnostic::from(err));
+        TokenStream::new()
+    });
 
     if let Some(layout) = compiler.layout.clone() {
         compiler.section_body = Some(ts.clone());
-        let layout_ts = compiler.compile(&layout)?;
+        ts = compiler.compile(&layout).unwrap_or_else(|err| {
+            compiler.diagnostics.push(Diagnostic::from(err));
+            TokenStream::new()
+        });
+    }
+
+    // the components are compiled into functions shared by their calls
+    let component_definitions = &compiler.component_definitions;
+    let ts = quote! {#component_definitions #ts};
 
-        return Ok((layout_ts, compiler.section_names(), compiler.text_size));
+    if !compiler.diagnostics.is_empty() {
+        let mut diagnostics = std::mem::take(&mut compiler.diagnostics);
+        rshtml_parser.sources.annotate(&mut diagnostics);
+        return Err(Diagnostics(diagnostics));
     }
 
     Ok((ts, compiler.section_names(), compiler.text_size))
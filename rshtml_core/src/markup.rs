@@ -0,0 +1,28 @@
+use crate::escape::Context;
+use crate::traits::Render;
+use std::fmt;
+use std::fmt::{Display, Write};
+
+/// A value that is already valid HTML, written into templates without escaping.
+///
+/// Wrap only trusted content, everything else should be rendered as a plain value so it gets escaped.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Markup<T = String>(pub T);
+
+impl<T> Markup<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: AsRef<str>> Markup<T> {
+    pub fn as_str(&self) -> &str {
+        self.0.as_ref()
+    }
+}
+
+impl<T: Display> Render for Markup<T> {
+    fn render_to<W: Write + ?Sized>(&self, f: &mut W, _: Context) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -1,5 +1,20 @@
+use std::fmt;
 use std::path::PathBuf;
 
+/// Where a node starts in its template, shown as `views/home.rs.html:42:17`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Position {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TextBlockItem {
     Text(String),
@@ -23,7 +38,7 @@ pub enum RustBlockContent {
 #[derive(Debug, PartialEq, Clone)]
 pub enum SectionDirectiveContent {
     Text(String),
-    RustExprSimple(String, bool),
+    RustExprSimple(String, bool, Position),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -42,27 +57,39 @@ pub struct ComponentParameter {
     pub value: ComponentParameterValue,
 }
 
+/// A parameter declared by `@props` in a component, like `count: i32 = 0`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ComponentProp {
+    pub name: String,
+    pub ty: String,
+    pub default: Option<String>,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Node {
     //IncludeDirective(PathBuf),         // include directive @include("other_view.html")
-    Template(Vec<Node>),                                   // main template, contains child nodes
-    Text(String),                                          // plain text content (@@ -> @)
-    InnerText(String),                                     // text inside a block (@@ -> @, @{ -> {, @} -> })
-    Comment(String),                                       // comment content
-    ExtendsDirective(PathBuf, Box<Node>),                  // extends directive @extends("layout.html")
-    RenderDirective(String),                               // yield directive @yield("content")
-    RustBlock(Vec<RustBlockContent>),                      // @{ ... } block content (with trim)
-    RustExprSimple(String, bool),                          // @expr ... (simple expression)
-    RustExprParen(String, bool),                           // @(expr) (expression parentheses)
-    MatchExpr(String, Vec<(String, Vec<Node>)>),           // @match expr { ... => ... }
-    RustExpr(Vec<(String, Vec<Node>)>),                    // @if ...  { ... } else { ... } / @for ... { ... }
-    SectionDirective(String, SectionDirectiveContent),     // @section("content")
-    SectionBlock(String, Vec<Node>),                       // @section content { ... }
-    RenderBody,                                            // @render_body (main body of subpage)
-    Component(String, Vec<ComponentParameter>, Vec<Node>), // @componentName(param1 = value1, param2 = value2) { ... } also <CompName p=""/> tags
-    ChildContent,                                          // @child_content (component child content)
-    Raw(String),                                           // @raw {} (raw content)
-    UseDirective(String, PathBuf, Box<Node>),              // @use "component.rs.html" as Component
-    ContinueDirective,                                     // @continue for the loops
-    BreakDirective,                                        // @break for the loops
+    Template(Vec<Node>),                                             // main template, contains child nodes
+    Text(String),                                                    // plain text content (@@ -> @)
+    InnerText(String),                                               // text inside a block (@@ -> @, @{ -> {, @} -> })
+    Comment(String),                                                 // comment content
+    ExtendsDirective(PathBuf, Box<Node>),                            // extends directive @extends("layout.html")
+    RenderDirective(String),                                         // yield directive @yield("content")
+    RustBlock(Vec<RustBlockContent>, Position),                      // @{ ... } block content (with trim)
+    RustExprSimple(String, bool, Position),                          // @expr ... (simple expression)
+    RustExprParen(String, bool, Position),                           // @(expr) (expression parentheses)
+    MatchExpr(String, Vec<(String, Vec<Node>)>, Position),           // @match expr { ... => ... }
+    RustExpr(Vec<(String, Vec<Node>)>, Position),                    // @if ...  { ... } else { ... } / @for ... { ... }
+    SectionDirective(String, SectionDirectiveContent),               // @section("content")
+    SectionBlock(String, Vec<Node>),                                 // @section content { ... }
+    RenderBody,                                                      // @render_body (main body of subpage)
+    Component(String, Vec<ComponentParameter>, Vec<Node>, Position), // @componentName(param1 = value1, param2 = value2) { ... } also <CompName p=""/> tags
+    ChildContent,                                                    // @child_content (component child content)
+    Slot(String, Vec<Node>, Position),                               // <slot:header> ... </slot:header> (named slot content in a component call)
+    SlotDirective(String, Position),                                 // @slot("header") (renders a named slot in a component)
+    PropsDirective(Vec<ComponentProp>, Position),                    // @props(title: &str, count: i32 = 0) (the parameters of a component)
+    Raw(String),                                                     // @raw {} (raw content)
+    UseDirective(String, PathBuf, Box<Node>, bool), // @use "component.rs.html" as Component (@use inline "..." compiles it at each call)
+    ContinueDirective,                              // @continue for the loops
+    BreakDirective,                                 // @break for the loops
+    FlushDirective,                                 // @flush (sends the output so far as a chunk when streaming)
 }
@@ -0,0 +1,132 @@
+use crate::Node;
+use crate::node::{ComponentParameter, ComponentParameterValue};
+
+/// Simplifies the tree before it is compiled, so the generated code writes static text in as few calls as possible.
+///
+/// Adjacent text, inner text and raw blocks become one text node, comments are dropped
+/// and unescaped literal expressions like `@#("abc")` are turned into text.
+pub struct Optimizer;
+
+impl Optimizer {
+    pub fn optimize(node: Node) -> Node {
+        match node {
+            Node::Template(nodes) => Node::Template(Self::optimize_nodes(nodes)),
+            Node::ExtendsDirective(path, layout) => Node::ExtendsDirective(path, Box::new(Self::optimize(*layout))),
+            node @ (Node::RustExprSimple(_, false, _) | Node::RustExprParen(_, false, _)) => Self::fold_literal(node),
+            Node::MatchExpr(name, arms, position) => Node::MatchExpr(
+                name,
+                arms.into_iter().map(|(head, nodes)| (head, Self::optimize_nodes(nodes))).collect(),
+                position,
+            ),
+            Node::RustExpr(exprs, position) => Node::RustExpr(
+                exprs.into_iter().map(|(head, nodes)| (head, Self::optimize_nodes(nodes))).collect(),
+                position,
+            ),
+            Node::SectionBlock(name, nodes) => Node::SectionBlock(name, Self::optimize_nodes(nodes)),
+            Node::Slot(name, nodes, position) => Node::Slot(name, Self::optimize_nodes(nodes), position),
+            Node::Component(name, parameters, body, position) => {
+                let parameters = parameters
+                    .into_iter()
+                    .map(|parameter| match parameter.value {
+                        ComponentParameterValue::Block(nodes) => ComponentParameter {
+                            name: parameter.name,
+                            value: ComponentParameterValue::Block(Self::optimize_nodes(nodes)),
+                        },
+                        _ => parameter,
+                    })
+                    .collect();
+
+                Node::Component(name, parameters, Self::optimize_nodes(body), position)
+            }
+            Node::UseDirective(name, path, component, inline) => Node::UseDirective(name, path, Box::new(Self::optimize(*component)), inline),
+            node => node,
+        }
+    }
+
+    fn optimize_nodes(nodes: Vec<Node>) -> Vec<Node> {
+        let mut optimized: Vec<Node> = Vec::with_capacity(nodes.len());
+
+        for node in nodes {
+            let text = match Self::optimize(node) {
+                Node::Comment(_) => continue,
+                Node::Text(text) | Node::InnerText(text) | Node::Raw(text) => text,
+                node => {
+                    optimized.push(node);
+                    continue;
+                }
+            };
+
+            match optimized.last_mut() {
+                Some(Node::Text(last)) => last.push_str(&text),
+                _ => optimized.push(Node::Text(text)),
+            }
+        }
+
+        optimized
+    }
+
+    fn fold_literal(node: Node) -> Node {
+        match &node {
+            Node::RustExprSimple(expr, _, _) | Node::RustExprParen(expr, _, _) => literal_value(expr).map(Node::Text).unwrap_or(node),
+            _ => node,
+        }
+    }
+
+    /// Replaces `@name` with the value of a literal component parameter (`title="home"`, `count=3`).
+    ///
+    /// Only the top level of the component is rewritten, up to its first code block, where the name can't have been shadowed.
+    pub fn inline_literal_parameters(component: &Node, parameters: &[ComponentParameter]) -> Node {
+        let Node::Template(nodes) = component else {
+            return component.clone();
+        };
+
+        let mut inlined = Vec::with_capacity(nodes.len());
+        let mut shadowed = false;
+
+        for node in nodes {
+            shadowed |= matches!(node, Node::RustBlock(..));
+
+            let literal = match node {
+                Node::RustExprSimple(expr, is_escaped, position) if !shadowed => parameters
+                    .iter()
+                    .find(|parameter| &parameter.name == expr)
+                    .and_then(|parameter| literal_parameter(&parameter.value))
+                    .map(|value| Node::RustExprParen(value, *is_escaped, position.clone())),
+                _ => None,
+            };
+
+            inlined.push(literal.unwrap_or_else(|| node.clone()));
+        }
+
+        Self::optimize(Node::Template(inlined))
+    }
+}
+
+/// The displayed value of a Rust literal expression, if `expr` is one.
+pub fn literal_value(expr: &str) -> Option<String> {
+    let mut expr = syn::parse_str::<syn::Expr>(expr).ok()?;
+
+    while let syn::Expr::Paren(paren) = expr {
+        expr = *paren.expr;
+    }
+
+    let syn::Expr::Lit(expr_lit) = expr else {
+        return None;
+    };
+
+    match expr_lit.lit {
+        syn::Lit::Str(lit) => Some(lit.value()),
+        syn::Lit::Char(lit) => Some(lit.value().to_string()),
+        syn::Lit::Int(lit) => Some(lit.base10_digits().to_string()),
+        syn::Lit::Bool(lit) => Some(lit.value.to_string()),
+        _ => None,
+    }
+}
+
+fn literal_parameter(value: &ComponentParameterValue) -> Option<String> {
+    match value {
+        ComponentParameterValue::Bool(value) => Some(value.to_string()),
+        ComponentParameterValue::Number(value) | ComponentParameterValue::String(value) => Some(format!("{:?}", value)),
+        _ => None,
+    }
+}
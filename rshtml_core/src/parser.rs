@@ -2,10 +2,12 @@ mod block;
 mod comment_block;
 mod component;
 mod component_tag;
+mod cursor;
 mod extends_directive;
 mod include_directive;
 mod inner_text;
 mod match_expr;
+mod props_directive;
 mod raw_block;
 mod render_directive;
 mod rust_block;
@@ -14,43 +16,37 @@ mod rust_expr_paren;
 mod rust_expr_simple;
 mod section_block;
 mod section_directive;
+mod slot_directive;
+mod slot_tag;
 mod template;
 mod text;
 mod use_directive;
 
 use crate::config::Config;
-use crate::error::rename_rules;
+use crate::diagnostic::{Diagnostic, Diagnostics, SourceMap};
+use crate::error::ParseError;
 use crate::node::*;
 use crate::parser::block::BlockParser;
 use crate::parser::comment_block::CommentBlockParser;
-use crate::parser::component::ComponentParser;
 use crate::parser::component_tag::ComponentTagParser;
-use crate::parser::extends_directive::ExtendsDirectiveParser;
-use crate::parser::include_directive::IncludeDirectiveParser;
+use crate::parser::cursor::Cursor;
 use crate::parser::inner_text::InnerTextParser;
-use crate::parser::match_expr::MatchExprParser;
-use crate::parser::raw_block::RawBlockParser;
-use crate::parser::render_directive::RenderDirectiveParser;
-use crate::parser::rust_block::RustBlockParser;
-use crate::parser::rust_expr::RustExprParser;
-use crate::parser::rust_expr_paren::RustExprParenParser;
-use crate::parser::rust_expr_simple::RustExprSimpleParser;
-use crate::parser::section_block::SectionBlockParser;
-use crate::parser::section_directive::SectionDirectiveParser;
+use crate::parser::slot_tag::SlotTagParser;
 use crate::parser::template::TemplateParser;
 use crate::parser::text::TextParser;
-use crate::parser::use_directive::UseDirectiveParser;
-use pest::error::{Error, ErrorVariant};
-use pest::iterators::{Pair, Pairs};
-use pest::{Parser, Position, Span};
-use pest_derive::Parser;
 use std::collections::HashSet;
 
-#[derive(Parser)]
-#[grammar = "rshtml.pest"]
+const INLINE_TEMPLATE: &str = "inline template";
+const MAX_RECOVERIES: usize = 64;
+
 pub struct RsHtmlParser {
     included_templates: HashSet<String>,
     config: Config,
+    file: String,
+    includes: Vec<Position>,
+    diagnostics: Vec<Diagnostic>,
+    recoveries: usize,
+    pub sources: SourceMap,
 }
 
 impl RsHtmlParser {
@@ -58,91 +54,202 @@ impl RsHtmlParser {
         Self {
             included_templates: HashSet::new(),
             config: Config::default(),
+            file: INLINE_TEMPLATE.to_string(),
+            includes: Vec::new(),
+            diagnostics: Vec::new(),
+            recoveries: 0,
+            sources: SourceMap::default(),
         }
     }
 
-    fn build_nodes_from_pairs(&mut self, pairs: Pairs<Rule>) -> Result<Vec<Node>, Box<Error<Rule>>> {
+    /// The position of `cursor` in the template being parsed.
+    fn position(&self, cursor: &Cursor) -> Position {
+        Position {
+            file: self.file.clone(),
+            line: cursor.line(),
+            column: cursor.column(),
+        }
+    }
+
+    /// Parses text, comments and blocks up to the end of `content`: the end of the input or a closing component tag,
+    /// and also a `}` in a block. A construct that fails to parse is reported and skipped, see [`Self::recover`].
+    fn parse_nodes(&mut self, cursor: &mut Cursor, content: Content) -> Vec<Node> {
         let mut nodes = Vec::new();
-        for pair in pairs {
-            match pair.as_rule() {
-                Rule::template_content => {
-                    let inner_nodes = self.build_nodes_from_pairs(pair.into_inner())?;
-                    nodes.extend(inner_nodes);
-                }
-                Rule::extends_directive | Rule::comment_block | Rule::block | Rule::text | Rule::inner_text => {
-                    nodes.push(self.build_ast_node(pair)?);
-                }
-                // skip other rules (EOI, WHITESPACE, etc.)
+
+        while let Some(c) = cursor.peek() {
+            let start = *cursor;
+            let result = match c {
+                '@' if cursor.starts_with("@*") => CommentBlockParser::parse(self, cursor),
+                '@' if !cursor.starts_with("@@") && !TextParser::is_literal(cursor, cursor) => BlockParser::parse(self, cursor),
+                '<' if ComponentTagParser::starts(cursor) => BlockParser::parse(self, cursor),
+                '<' if SlotTagParser::starts(cursor) => SlotTagParser::parse(self, cursor),
+                '<' if ComponentTagParser::closes(cursor) || SlotTagParser::closes(cursor) => break,
+                '}' if content == Content::Block => break,
+                _ if content == Content::Block => InnerTextParser::parse(self, cursor),
+                _ => TextParser::parse(self, cursor),
+            };
+
+            match result {
+                Ok(node) => nodes.push(node),
+                Err(err) => self.recover(cursor, &start, err),
+            }
+        }
+
+        nodes
+    }
+
+    /// Reports `err`, raised by the construct starting at `start`, and moves the cursor to where parsing can resume:
+    /// past the `}` closing a block the construct opened before the error, or else the next `@`, `<` or `}` after it.
+    ///
+    /// Once `MAX_RECOVERIES` errors have been reported, the rest of the source is skipped and that is reported instead.
+    fn recover<'a>(&mut self, cursor: &mut Cursor<'a>, start: &Cursor<'a>, err: ParseError) {
+        self.recoveries += 1;
+        if self.recoveries > MAX_RECOVERIES {
+            if self.recoveries == MAX_RECOVERIES + 1 {
+                let message = format!("too many errors, the rest of {} isn't checked", self.file);
+                self.report(Diagnostic::from_parse_error(ParseError { message, ..err }, &self.file));
+            }
+            while cursor.bump().is_some() {}
+            return;
+        }
+
+        let mut resume = *start;
+        let mut depth = 0;
+        while resume.offset() < err.offset
+            && let Some(c) = resume.bump()
+        {
+            match c {
+                '{' => depth += 1,
+                '}' if depth > 0 => depth -= 1,
                 _ => {}
             }
         }
+        if resume.offset() == start.offset() {
+            resume.bump();
+        }
+
+        if depth > 0 {
+            while let Some(c) = resume.bump() {
+                match c {
+                    '{' => depth += 1,
+                    '}' if depth == 1 => break,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+            }
+        } else {
+            while resume.peek().is_some_and(|c| !matches!(c, '@' | '<' | '}')) {
+                resume.bump();
+            }
+        }
+
+        self.report(Diagnostic::from_parse_error(err, &self.file));
+        *cursor = resume;
+    }
+
+    /// Parses the body of a `{ ... }` block whose `{` was just read, up to and including its `}`.
+    fn parse_block_body(&mut self, cursor: &mut Cursor, directive: &str) -> Result<Vec<Node>, ParseError> {
+        let nodes = self.parse_nodes(cursor, Content::Block);
+
+        if !cursor.eat("}") {
+            return Err(cursor.error(format!("expected `}}` to close {}", directive)));
+        }
+
         Ok(nodes)
     }
 
-    fn build_ast_node(&mut self, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        match pair.as_rule() {
-            Rule::template => TemplateParser::parse(self, pair),
-            Rule::text => TextParser::parse(self, pair),
-            Rule::inner_text => InnerTextParser::parse(self, pair),
-            Rule::comment_block => CommentBlockParser::parse(self, pair),
-            Rule::block => BlockParser::parse(self, pair),
-            Rule::include_directive => IncludeDirectiveParser::parse(self, pair),
-            Rule::render_directive => RenderDirectiveParser::parse(self, pair),
-            Rule::render_body_directive => Ok(Node::RenderBody),
-            Rule::extends_directive => ExtendsDirectiveParser::parse(self, pair),
-            Rule::rust_block => RustBlockParser::parse(self, pair),
-            Rule::rust_expr_simple => RustExprSimpleParser::parse(self, pair),
-            Rule::rust_expr_paren => RustExprParenParser::parse(self, pair),
-            Rule::rust_expr => RustExprParser::parse(self, pair),
-            Rule::match_expr => MatchExprParser::parse(self, pair),
-            Rule::section_directive => SectionDirectiveParser::parse(self, pair),
-            Rule::section_block => SectionBlockParser::parse(self, pair),
-            Rule::component => ComponentParser::parse(self, pair),
-            Rule::component_tag => ComponentTagParser::parse(self, pair),
-            Rule::child_content_directive => Ok(Node::ChildContent),
-            Rule::raw_block => RawBlockParser::parse(self, pair),
-            Rule::use_directive => UseDirectiveParser::parse(self, pair),
-            Rule::continue_directive => Ok(Node::ContinueDirective),
-            Rule::break_directive => Ok(Node::BreakDirective),
-            rule => Err(Box::new(Error::new_from_span(
-                ErrorVariant::CustomError {
-                    message: format!("Error: Unknown rule: {:?}", rule),
-                },
-                pair.as_span(),
-            ))),
+    /// Whether `name(` starts at the cursor, like `include(` and `render(`, with optional whitespace before the parenthesis.
+    fn starts_call(cursor: &Cursor, name: &str) -> bool {
+        let mut c = *cursor;
+        c.eat(name) && {
+            c.skip_whitespace();
+            c.starts_with("(")
+        }
+    }
+
+    /// Reads the opening parenthesis of `directive` and the quoted string after it.
+    fn parse_string_argument_start(cursor: &mut Cursor, directive: &str, expected: &str) -> Result<String, ParseError> {
+        cursor.eat(directive);
+        cursor.skip_whitespace();
+        cursor.eat("(");
+        cursor.skip_whitespace();
+
+        match cursor.eat_quoted()? {
+            Some(string) => Ok(string.trim_matches('"').trim_matches('\'').to_string()),
+            None => Err(cursor.error(format!("expected {} in quotes in @{}", expected, directive))),
         }
     }
 
-    fn parse_template(&mut self, path: &str) -> Result<Node, Box<Error<Rule>>> {
-        let input = self.read_template(path).map_err(|err| {
-            Error::new_from_span(
-                ErrorVariant::CustomError {
-                    message: format!("Error reading template: {:?}, path: {}", err, path),
+    /// Reads `directive("argument")` and returns the argument without its quotes.
+    fn parse_string_argument(cursor: &mut Cursor, directive: &str, expected: &str) -> Result<String, ParseError> {
+        let argument = Self::parse_string_argument_start(cursor, directive, expected)?;
+        Self::parse_closing_parenthesis(cursor, directive)?;
+
+        Ok(argument)
+    }
+
+    fn parse_closing_parenthesis(cursor: &mut Cursor, directive: &str) -> Result<(), ParseError> {
+        cursor.skip_whitespace();
+        if !cursor.eat(")") {
+            return Err(cursor.error(format!("expected `)` to close @{}", directive)));
+        }
+
+        Ok(())
+    }
+
+    fn parse_template(&mut self, path: &str) -> Result<Node, Diagnostic> {
+        let view_path = self.config.views.0.join(path);
+        let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+        let file = view_path.strip_prefix(&manifest_dir).unwrap_or(&view_path).to_string_lossy().to_string();
+
+        let input = self.read_template(path).map_err(|err| Diagnostic {
+            included_from: self.includes.iter().rev().cloned().collect(),
+            ..Diagnostic::new(
+                err,
+                Position {
+                    file: file.clone(),
+                    line: 0,
+                    column: 0,
                 },
-                Span::new(path, 0, 0).unwrap(),
             )
         })?;
 
-        let mut pairs = Self::parse(Rule::template, &input)?;
-        let template_pair = pairs.next().ok_or(Error::new_from_pos(
-            ErrorVariant::CustomError {
-                message: "Error: Empty template".to_string(),
-            },
-            Position::new("Template", 0).unwrap(),
-        ))?;
-
-        if template_pair.as_rule() == Rule::template {
-            let ast = self.build_ast_node(template_pair)?;
-            Ok(ast)
-        } else {
-            let err: Error<Rule> = Error::new_from_span(
-                ErrorVariant::ParsingError {
-                    positives: vec![Rule::template],
-                    negatives: vec![],
-                },
-                template_pair.as_span(),
-            );
-            Err(Box::new(err))
+        let parent_file = std::mem::replace(&mut self.file, file);
+        let node = self.parse_source(&input);
+        self.file = parent_file;
+
+        Ok(node)
+    }
+
+    /// Parses a template used by an `@include`, `@use` or `@extends` directive at `position`.
+    ///
+    /// Its errors are recorded with the include chain and an empty template takes its place,
+    /// so the rest of the including template is still checked.
+    fn parse_included(&mut self, path: &str, position: Position) -> Node {
+        self.includes.push(position);
+        let result = self.parse_template(path);
+        self.includes.pop();
+
+        result.unwrap_or_else(|diagnostic| {
+            self.report(diagnostic);
+            Node::Template(Vec::new())
+        })
+    }
+
+    /// Parses `input`, recovering from errors so that one bad directive doesn't hide the rest.
+    /// Returns what could be parsed, the errors are recorded.
+    fn parse_source(&mut self, input: &str) -> Node {
+        self.sources.insert(&self.file, input, &self.includes);
+
+        TemplateParser::parse(self, &mut Cursor::new(input)).unwrap_or_else(|err| {
+            self.report(Diagnostic::from_parse_error(err, &self.file));
+            Node::Template(Vec::new())
+        })
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        if !self.diagnostics.contains(&diagnostic) {
+            self.diagnostics.push(diagnostic);
         }
     }
 
@@ -154,12 +261,55 @@ impl RsHtmlParser {
         Ok(template)
     }
 
-    pub fn run(&mut self, path: &str, config: Config) -> Result<Node, Box<Error<Rule>>> {
+    pub fn run(&mut self, path: &str, config: Config) -> Result<Node, Diagnostics> {
+        let (node, diagnostics) = self.run_partial(path, config);
+        if diagnostics.0.is_empty() { Ok(node) } else { Err(diagnostics) }
+    }
+
+    /// Parses a template given as a string instead of a file in the views folder.
+    pub fn run_source(&mut self, source: &str, config: Config) -> Result<Node, Diagnostics> {
+        self.config = config;
+        let node = self.parse_source(source);
+        let diagnostics = self.take_diagnostics();
+        if diagnostics.0.is_empty() { Ok(node) } else { Err(diagnostics) }
+    }
+
+    /// Parses as much of a template as possible, returning the partial tree along with every error found,
+    /// including those of the templates it uses. Meant for tools working on half-written templates.
+    pub fn run_partial(&mut self, path: &str, config: Config) -> (Node, Diagnostics) {
         self.config = config;
-        self.parse_template(path).map_err(|err| rename_rules(*err))
+        let node = self.parse_template(path).unwrap_or_else(|diagnostic| {
+            self.report(diagnostic);
+            Node::Template(Vec::new())
+        });
+
+        (node, self.take_diagnostics())
+    }
+
+    fn take_diagnostics(&mut self) -> Diagnostics {
+        let mut diagnostics = std::mem::take(&mut self.diagnostics);
+        self.sources.annotate(&mut diagnostics);
+        Diagnostics(diagnostics)
+    }
+}
+
+impl Default for RsHtmlParser {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
+/// What a run of template content is made of and what ends it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Content {
+    /// The template itself and the body of component tags, made of `Text` nodes.
+    Template,
+    /// The body of a `{ ... }` block, made of `InnerText` nodes and ended by its `}`.
+    Block,
+}
+
+/// Parses one construct, with the cursor at its start. The caller has checked that it starts there,
+/// so an error means the construct is malformed.
 pub trait IParser {
-    fn parse(parser: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>>;
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError>;
 }
@@ -1,19 +1,125 @@
 ﻿use crate::Node;
-use crate::parser::{IParser, RsHtmlParser, Rule};
-use pest::error::{Error, ErrorVariant};
-use pest::iterators::Pair;
+use crate::error::ParseError;
+use crate::parser::component::ComponentParser;
+use crate::parser::component_tag::ComponentTagParser;
+use crate::parser::cursor::{Cursor, is_whitespace};
+use crate::parser::include_directive::IncludeDirectiveParser;
+use crate::parser::match_expr::MatchExprParser;
+use crate::parser::props_directive::PropsDirectiveParser;
+use crate::parser::raw_block::RawBlockParser;
+use crate::parser::render_directive::RenderDirectiveParser;
+use crate::parser::rust_block::RustBlockParser;
+use crate::parser::rust_expr::RustExprParser;
+use crate::parser::rust_expr_paren::RustExprParenParser;
+use crate::parser::rust_expr_simple::RustExprSimpleParser;
+use crate::parser::section_block::SectionBlockParser;
+use crate::parser::section_directive::SectionDirectiveParser;
+use crate::parser::slot_directive::SlotDirectiveParser;
+use crate::parser::use_directive::UseDirectiveParser;
+use crate::parser::{IParser, RsHtmlParser};
 
 pub struct BlockParser;
 
 impl IParser for BlockParser {
-    fn parse(parser: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let pair_span = pair.as_span();
-
-        parser.build_ast_node(pair.into_inner().next().ok_or(Error::new_from_span(
-            ErrorVariant::CustomError {
-                message: "Error: Empty block".to_string(),
-            },
-            pair_span,
-        ))?)
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        if ComponentTagParser::starts(cursor) {
+            return ComponentTagParser::parse(parser, cursor);
+        }
+
+        let at = *cursor;
+        cursor.eat("@");
+        cursor.skip_whitespace();
+
+        let c = *cursor;
+        if RawBlockParser::starts(&c) {
+            RawBlockParser::parse(parser, cursor)
+        } else if RenderDirectiveParser::starts(&c) {
+            RenderDirectiveParser::parse(parser, cursor)
+        } else if SlotDirectiveParser::starts(&c) {
+            SlotDirectiveParser::parse(parser, cursor)
+        } else if IncludeDirectiveParser::starts(&c) {
+            IncludeDirectiveParser::parse(parser, cursor)
+        } else if SectionDirectiveParser::starts(&c) {
+            SectionDirectiveParser::parse(parser, cursor)
+        } else if SectionBlockParser::starts(&c) {
+            SectionBlockParser::parse(parser, cursor)
+        } else if Self::eat_bare_directive(cursor, "render_body") {
+            Ok(Node::RenderBody)
+        } else if Self::eat_bare_directive(cursor, "child_content") {
+            Ok(Node::ChildContent)
+        } else if Self::eat_bare_directive(cursor, "flush") {
+            Ok(Node::FlushDirective)
+        } else if PropsDirectiveParser::starts(&c) {
+            PropsDirectiveParser::parse(parser, cursor)
+        } else if ComponentParser::starts(&c) {
+            ComponentParser::parse(parser, cursor)
+        } else if UseDirectiveParser::starts(&c) {
+            UseDirectiveParser::parse(parser, cursor)
+        } else if RustBlockParser::starts(&c) {
+            RustBlockParser::parse(parser, cursor)
+        } else if RustExprParser::starts(&c) {
+            RustExprParser::parse(parser, cursor)
+        } else if RustExprParenParser::starts(&c) {
+            RustExprParenParser::parse(parser, cursor)
+        } else if MatchExprParser::starts(&c) {
+            MatchExprParser::parse(parser, cursor)
+        } else if let Some(node) = Self::eat_loop_directive(cursor) {
+            Ok(node)
+        } else if RustExprSimpleParser::starts(&c) {
+            RustExprSimpleParser::parse(parser, cursor)
+        } else {
+            Err(Self::error(&at, cursor))
+        }
+    }
+}
+
+impl BlockParser {
+    /// `@render_body`, `@child_content` and `@flush`, followed by whitespace, the end of the input or `()`.
+    fn eat_bare_directive(cursor: &mut Cursor, name: &str) -> bool {
+        let mut c = *cursor;
+        if !c.eat(name) {
+            return false;
+        }
+        if c.is_eof() || c.peek().is_some_and(is_whitespace) || c.eat("()") {
+            *cursor = c;
+            return true;
+        }
+        false
+    }
+
+    /// `continue` and `break`, which also take the whitespace after them.
+    pub fn eat_loop_directive(cursor: &mut Cursor) -> Option<Node> {
+        let node = if cursor.eat_keyword("continue") {
+            Node::ContinueDirective
+        } else if cursor.eat_keyword("break") {
+            Node::BreakDirective
+        } else {
+            return None;
+        };
+
+        cursor.skip_whitespace();
+        Some(node)
+    }
+
+    /// The error for an `@` that doesn't start anything, naming the directive when it's one used the wrong way.
+    fn error(at: &Cursor, cursor: &Cursor) -> ParseError {
+        let mut c = *cursor;
+        let message = match c.eat_identifier() {
+            Some("extends") => "@extends must come before the content of the template",
+            Some("raw") => "expected `{` after @raw",
+            Some("include") => "expected `(` and a path after @include",
+            Some("render") => "expected `(` and a section name after @render",
+            Some("section") => "expected `(` or a section name after @section",
+            Some("if") | Some("for") | Some("while") => "expected a condition and a `{` block",
+            Some("else") => "expected `{` or `if` after @else",
+            Some("match") => "expected an expression after @match",
+            Some("use") => "expected a path after @use",
+            _ => "expected an expression or a directive after `@`, write `@@` for a literal `@`",
+        };
+
+        match c.since(cursor) {
+            "" => at.error(message),
+            keyword => at.error(message).with_length(keyword.len() + cursor.offset() - at.offset()),
+        }
     }
 }
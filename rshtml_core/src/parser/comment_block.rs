@@ -1,19 +1,24 @@
 ﻿use crate::Node;
-use crate::parser::{IParser, RsHtmlParser, Rule};
-use pest::error::{Error, ErrorVariant};
-use pest::iterators::Pair;
+use crate::error::ParseError;
+use crate::parser::cursor::Cursor;
+use crate::parser::{IParser, RsHtmlParser};
 
 pub struct CommentBlockParser;
 
 impl IParser for CommentBlockParser {
-    fn parse(_: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let span = pair.as_span();
+    fn parse(_: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let start = *cursor;
+        cursor.eat("@*");
 
-        Ok(Node::Comment(
-            pair.into_inner()
-                .find(|p| p.as_rule() == Rule::comment_content)
-                .map(|p| p.as_str().to_string())
-                .ok_or(Error::new_from_span(ErrorVariant::CustomError { message: "".into() }, span))?,
-        ))
+        let content = *cursor;
+        while !cursor.starts_with("*@") {
+            if cursor.bump().is_none() {
+                return Err(start.error("unclosed comment, expected `*@`").with_length(2));
+            }
+        }
+        let comment = cursor.since(&content).to_string();
+        cursor.eat("*@");
+
+        Ok(Node::Comment(comment))
     }
 }
@@ -1,102 +1,174 @@
 ﻿use crate::Node;
+use crate::error::ParseError;
 use crate::node::{ComponentParameter, ComponentParameterValue};
-use crate::parser::{IParser, RsHtmlParser, Rule};
-use pest::error::{Error, ErrorVariant};
-use pest::iterators::Pair;
+use crate::parser::cursor::Cursor;
+use crate::parser::rust_expr_paren::RustExprParenParser;
+use crate::parser::rust_expr_simple::RustExprSimpleParser;
+use crate::parser::{IParser, RsHtmlParser};
 
 pub struct ComponentParser;
 
 impl IParser for ComponentParser {
-    fn parse(parser: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let pair_span = pair.as_span();
-
-        let component_name_pair = pair
-            .clone()
-            .into_inner()
-            .find(|p| p.as_rule() == Rule::rust_identifier)
-            .ok_or(Error::new_from_span(
-                ErrorVariant::ParsingError {
-                    positives: vec![Rule::rust_identifier],
-                    negatives: vec![],
-                },
-                pair_span,
-            ))?;
-        let component_name = component_name_pair.as_str().to_string();
-
-        let component_parameter_pairs = pair.clone().into_inner().filter(|p| p.as_rule() == Rule::component_parameter);
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let position = parser.position(cursor);
+
+        let component_name = cursor.eat_identifier().unwrap_or_default().to_string();
+        cursor.skip_whitespace();
+        cursor.eat("(");
+        cursor.skip_whitespace();
 
         let mut component_parameters = Vec::new();
-        for pair in component_parameter_pairs {
-            let pair_name = pair
-                .clone()
-                .into_inner()
-                .find(|p| p.as_rule() == Rule::rust_identifier)
-                .ok_or(Error::new_from_span(
-                    ErrorVariant::ParsingError {
-                        positives: vec![Rule::rust_identifier],
-                        negatives: vec![],
-                    },
-                    pair_span,
-                ))?;
-            let pair_value = pair
-                .clone()
-                .into_inner()
-                .find(|p| p.as_rule() != Rule::rust_identifier)
-                .ok_or(Error::new_from_span(
-                    ErrorVariant::ParsingError {
-                        positives: vec![
-                            Rule::bool,
-                            Rule::number,
-                            Rule::string,
-                            Rule::rust_expr_simple,
-                            Rule::rust_expr_paren,
-                            Rule::inner_template,
-                        ],
-                        negatives: vec![Rule::rust_identifier],
-                    },
-                    pair_span,
-                ))?;
-
-            let value = Self::build_component_parameter_value(parser, pair_value)?;
-            let name = pair_name.as_str().to_string();
-
-            component_parameters.push(ComponentParameter { name, value });
+        loop {
+            let mut c = *cursor;
+            if !component_parameters.is_empty() {
+                c.skip_whitespace();
+                if c.eat(",") {
+                    c.skip_whitespace();
+                }
+            }
+
+            let Some(name) = Self::eat_parameter_name(&mut c) else {
+                break;
+            };
+            *cursor = c;
+
+            let value = Self::build_component_parameter_value(parser, cursor, name, true)?.ok_or_else(|| {
+                cursor.error(format!(
+                    "expected a value for the parameter `{}` of @{}: {}",
+                    name,
+                    component_name,
+                    Self::VALUES
+                ))
+            })?;
+
+            component_parameters.push(ComponentParameter {
+                name: name.to_string(),
+                value,
+            });
         }
 
-        let content_pairs = pair.into_inner().find(|x| x.as_rule() == Rule::inner_template).ok_or(Error::new_from_span(
-            ErrorVariant::ParsingError {
-                positives: vec![Rule::inner_template],
-                negatives: vec![],
-            },
-            pair_span,
-        ))?;
+        RsHtmlParser::parse_closing_parenthesis(cursor, &component_name)?;
 
-        let body = parser.build_nodes_from_pairs(content_pairs.into_inner())?;
-        Ok(Node::Component(component_name, component_parameters, body))
+        cursor.skip_whitespace();
+        if !cursor.eat("{") {
+            return Err(cursor.error(format!("expected `{{` after the parameters of @{}", component_name)));
+        }
+        cursor.skip_whitespace();
+
+        let body = parser.parse_block_body(cursor, &format!("@{}", component_name))?;
+        Ok(Node::Component(component_name, component_parameters, body, position))
     }
 }
 
 impl ComponentParser {
-    pub fn build_component_parameter_value(parser: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<ComponentParameterValue, Box<Error<Rule>>> {
-        match pair.as_rule() {
-            Rule::bool => Ok(ComponentParameterValue::Bool(pair.as_str() == "true")),
-            Rule::number => Ok(ComponentParameterValue::Number(pair.as_str().to_string())),
-            Rule::string => {
-                let raw_str = pair.as_str().trim_matches('"').trim_matches('\'');
-                Ok(ComponentParameterValue::String(raw_str.to_string()))
+    pub const VALUES: &str = "true, false, a number, a string, an @expression or a { block }";
+
+    /// `name(` followed by `name:`, or by `)` and the `{` of the body, so calls like `@format(x)` are expressions.
+    pub fn starts(cursor: &Cursor) -> bool {
+        let mut c = *cursor;
+        if c.eat_identifier().is_none() {
+            return false;
+        }
+        c.skip_whitespace();
+        if !c.eat("(") {
+            return false;
+        }
+        c.skip_whitespace();
+
+        if c.eat(")") {
+            c.skip_whitespace();
+            return c.starts_with("{");
+        }
+
+        Self::eat_parameter_name(&mut c).is_some()
+    }
+
+    /// `name:`, and the whitespace after it.
+    fn eat_parameter_name<'a>(cursor: &mut Cursor<'a>) -> Option<&'a str> {
+        let mut c = *cursor;
+        let name = c.eat_identifier()?;
+        c.skip_whitespace();
+        if !c.eat(":") || c.starts_with(":") {
+            return None;
+        }
+        c.skip_whitespace();
+
+        *cursor = c;
+        Some(name)
+    }
+
+    /// Reads the value of a parameter or attribute, `None` when there's no value at the cursor.
+    ///
+    /// In `@component(...)` calls, `in_call`, whitespace is allowed after the `@` of expressions and the `{` of blocks.
+    pub fn build_component_parameter_value(
+        parser: &mut RsHtmlParser,
+        cursor: &mut Cursor,
+        name: &str,
+        in_call: bool,
+    ) -> Result<Option<ComponentParameterValue>, ParseError> {
+        if let Some(value) = ["true", "false"].into_iter().find(|value| cursor.eat(value)) {
+            return Ok(Some(ComponentParameterValue::Bool(value == "true")));
+        }
+
+        if let Some(number) = Self::eat_number(cursor) {
+            return Ok(Some(ComponentParameterValue::Number(number.to_string())));
+        }
+
+        let start = *cursor;
+        if cursor.eat_char_or_string() {
+            let raw_str = cursor.since(&start).trim_matches('"').trim_matches('\'');
+            return Ok(Some(ComponentParameterValue::String(raw_str.to_string())));
+        }
+
+        let mut expr = *cursor;
+        if expr.eat("@") {
+            if in_call {
+                expr.skip_whitespace();
+            }
+            if let Some(paren) = RustExprParenParser::scan(&mut expr)? {
+                *cursor = expr;
+                return Ok(Some(ComponentParameterValue::RustExprParen(paren.to_string())));
+            }
+            if RustExprSimpleParser::starts(&expr) {
+                let simple = RustExprSimpleParser::scan(&mut expr);
+                *cursor = expr;
+                return Ok(Some(ComponentParameterValue::RustExprSimple(simple.to_string())));
+            }
+        }
+
+        if cursor.eat("{") {
+            if in_call {
+                cursor.skip_whitespace();
             }
-            Rule::rust_expr_simple => Ok(ComponentParameterValue::RustExprSimple(pair.as_str().to_string())),
-            Rule::rust_expr_paren => Ok(ComponentParameterValue::RustExprParen(pair.as_str().to_string())),
-            Rule::inner_template => {
-                let block_nodes = parser.build_nodes_from_pairs(pair.into_inner())?;
-                Ok(ComponentParameterValue::Block(block_nodes))
+            let block_nodes = parser.parse_block_body(cursor, &format!("the block of `{}`", name))?;
+            return Ok(Some(ComponentParameterValue::Block(block_nodes)));
+        }
+
+        Ok(None)
+    }
+
+    /// `-?[0-9]+(\.[0-9]+)?`
+    fn eat_number<'a>(cursor: &mut Cursor<'a>) -> Option<&'a str> {
+        let mut c = *cursor;
+        c.eat("-");
+        let digits = |c: &mut Cursor| {
+            let start = c.offset();
+            while c.peek().is_some_and(|c| c.is_ascii_digit()) {
+                c.bump();
             }
-            rule => Err(Box::new(Error::new_from_span(
-                ErrorVariant::CustomError {
-                    message: format!("Unexpected rule: {:?}", rule),
-                },
-                pair.as_span(),
-            ))),
+            c.offset() > start
+        };
+
+        if !digits(&mut c) {
+            return None;
         }
+        let mut fraction = c;
+        if fraction.eat(".") && digits(&mut fraction) {
+            c = fraction;
+        }
+
+        let number = c.since(cursor);
+        *cursor = c;
+        Some(number)
     }
 }
@@ -1,56 +1,123 @@
 ﻿use crate::Node;
+use crate::error::ParseError;
 use crate::node::{ComponentParameter, ComponentParameterValue};
 use crate::parser::component::ComponentParser;
-use crate::parser::{IParser, RsHtmlParser, Rule};
-use pest::error::{Error, ErrorVariant};
-use pest::iterators::Pair;
+use crate::parser::cursor::Cursor;
+use crate::parser::{Content, IParser, RsHtmlParser};
 
 pub struct ComponentTagParser;
 
 impl IParser for ComponentTagParser {
-    fn parse(parser: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let pair_span = pair.as_span();
-        let mut inner_pairs = pair.into_inner();
-        let component_name_pair = inner_pairs.find(|p| p.as_rule() == Rule::component_tag_name).ok_or(Error::new_from_span(
-            ErrorVariant::ParsingError {
-                positives: vec![Rule::component_tag_name],
-                negatives: vec![],
-            },
-            pair_span,
-        ))?;
-        let component_name = component_name_pair.as_str().to_string();
-
-        let component_parameter_pairs = inner_pairs.clone().filter(|p| p.as_rule() == Rule::attribute);
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let position = parser.position(cursor);
+        let open = *cursor;
+        cursor.eat("<");
+        let component_name = Self::eat_tag_name(cursor).unwrap_or_default().to_string();
 
         let mut component_parameters = Vec::new();
-        for pair in component_parameter_pairs {
-            let pair_name = pair
-                .clone()
-                .into_inner()
-                .find(|p| p.as_rule() == Rule::attribute_name)
-                .ok_or(Error::new_from_span(
-                    ErrorVariant::ParsingError {
-                        positives: vec![Rule::attribute_name],
-                        negatives: vec![],
-                    },
-                    pair_span,
-                ))?;
-
-            let value = match pair.clone().into_inner().find(|p| p.as_rule() != Rule::attribute_name) {
-                Some(pair_value) => ComponentParser::build_component_parameter_value(parser, pair_value)?,
-                None => ComponentParameterValue::Bool(true),
+        loop {
+            let mut c = *cursor;
+            if !c.eat_whitespace() {
+                break;
+            }
+            let Some(name) = Self::eat_attribute_name(&mut c) else {
+                break;
             };
+            *cursor = c;
 
-            let name = pair_name.as_str().to_string();
+            let mut value_cursor = *cursor;
+            value_cursor.skip_whitespace();
+            let value = if value_cursor.eat("=") {
+                value_cursor.skip_whitespace();
+                *cursor = value_cursor;
+                ComponentParser::build_component_parameter_value(parser, cursor, name, false)?.ok_or_else(|| {
+                    cursor.error(format!(
+                        "expected a value for the attribute `{}` of <{}>: {}",
+                        name,
+                        component_name,
+                        ComponentParser::VALUES
+                    ))
+                })?
+            } else {
+                ComponentParameterValue::Bool(true)
+            };
 
-            component_parameters.push(ComponentParameter { name, value });
+            component_parameters.push(ComponentParameter {
+                name: name.to_string(),
+                value,
+            });
         }
 
-        let body = match inner_pairs.find(|x| x.as_rule() == Rule::tag_template) {
-            Some(tag_template) => parser.build_nodes_from_pairs(tag_template.into_inner())?,
-            None => vec![],
+        cursor.skip_whitespace();
+        let body = if cursor.eat("/>") {
+            vec![]
+        } else if cursor.eat(">") {
+            let body = parser.parse_nodes(cursor, Content::Template);
+
+            let close = *cursor;
+            let closing_name = cursor.eat("</").then(|| Self::eat_tag_name(cursor)).flatten();
+            if closing_name != Some(component_name.as_str()) || !cursor.eat(">") {
+                let length = if closing_name.is_some() { cursor.offset() - close.offset() } else { 1 };
+                return Err(close
+                    .error(format!(
+                        "expected `</{}>` to close the <{}> tag opened at {}:{}",
+                        component_name,
+                        component_name,
+                        open.line(),
+                        open.column()
+                    ))
+                    .with_length(length));
+            }
+
+            body
+        } else {
+            return Err(cursor.error(format!("expected `>` or `/>` to end the <{}> tag", component_name)));
         };
 
-        Ok(Node::Component(component_name, component_parameters, body))
+        Ok(Node::Component(component_name, component_parameters, body, position))
+    }
+}
+
+impl ComponentTagParser {
+    /// `<` and an uppercase letter, as in `<Card`.
+    pub fn starts(cursor: &Cursor) -> bool {
+        cursor.starts_with("<") && cursor.peek_nth(1).is_some_and(|c| c.is_ascii_uppercase())
+    }
+
+    /// `</` and an uppercase letter, as in `</Card>`.
+    pub fn closes(cursor: &Cursor) -> bool {
+        cursor.starts_with("</") && cursor.peek_nth(2).is_some_and(|c| c.is_ascii_uppercase())
+    }
+
+    /// `Card` or `Layout.Header`, every part starting with an uppercase letter.
+    fn eat_tag_name<'a>(cursor: &mut Cursor<'a>) -> Option<&'a str> {
+        let start = *cursor;
+        loop {
+            if !cursor.peek().is_some_and(|c| c.is_ascii_uppercase()) {
+                *cursor = start;
+                return None;
+            }
+            while cursor.peek().is_some_and(|c| c.is_ascii_alphanumeric()) {
+                cursor.bump();
+            }
+
+            let mut next = *cursor;
+            if !(next.eat(".") && next.peek().is_some_and(|c| c.is_ascii_uppercase())) {
+                return Some(cursor.since(&start));
+            }
+            *cursor = next;
+        }
+    }
+
+    /// `[A-Za-z_][A-Za-z0-9_-]*`
+    fn eat_attribute_name<'a>(cursor: &mut Cursor<'a>) -> Option<&'a str> {
+        let start = *cursor;
+        if !cursor.peek().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') {
+            return None;
+        }
+        while cursor.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            cursor.bump();
+        }
+        Some(cursor.since(&start))
     }
 }
@@ -0,0 +1,352 @@
+use crate::error::ParseError;
+
+/// A position in the source being parsed, keeping track of its line and column as it moves, and of whether it is in a
+/// `<style>` element.
+///
+/// It is `Copy`, so trying a construct and backtracking is saving a cursor and restoring it.
+#[derive(Debug, Clone, Copy)]
+pub struct Cursor<'a> {
+    source: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+    in_style: bool,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Cursor {
+            source,
+            offset: 0,
+            line: 1,
+            column: 1,
+            in_style: false,
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Whether the cursor is past a `<style` tag and not past the `</style` tag closing it.
+    pub fn in_style(&self) -> bool {
+        self.in_style
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.source[self.offset..]
+    }
+
+    /// The source before the cursor.
+    pub fn before(&self) -> &'a str {
+        &self.source[..self.offset]
+    }
+
+    /// The source between `start` and the cursor.
+    pub fn since(&self, start: &Cursor) -> &'a str {
+        &self.source[start.offset..self.offset]
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.offset >= self.source.len()
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    pub fn peek_nth(&self, n: usize) -> Option<char> {
+        self.rest().chars().nth(n)
+    }
+
+    pub fn starts_with(&self, s: &str) -> bool {
+        self.rest().starts_with(s)
+    }
+
+    pub fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        if c == '<' {
+            let tag_is = |name: &str| self.rest().get(..name.len()).is_some_and(|tag| tag.eq_ignore_ascii_case(name));
+            if tag_is("style") {
+                self.in_style = true;
+            } else if tag_is("/style") {
+                self.in_style = false;
+            }
+        }
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    pub fn eat(&mut self, s: &str) -> bool {
+        if !self.starts_with(s) {
+            return false;
+        }
+        let end = self.offset + s.len();
+        while self.offset < end {
+            self.bump();
+        }
+        true
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(is_whitespace) {
+            self.bump();
+        }
+    }
+
+    /// Skips at least one whitespace character.
+    pub fn eat_whitespace(&mut self) -> bool {
+        let start = self.offset;
+        self.skip_whitespace();
+        self.offset > start
+    }
+
+    /// `[A-Za-z_][A-Za-z0-9_]*`
+    pub fn eat_identifier(&mut self) -> Option<&'a str> {
+        let start = *self;
+        if !self.peek().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') {
+            return None;
+        }
+        while self.peek().is_some_and(is_identifier_char) {
+            self.bump();
+        }
+        Some(self.since(&start))
+    }
+
+    /// Eats `keyword` when it isn't the start of a longer identifier.
+    pub fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let mut cursor = *self;
+        if cursor.eat(keyword) && !cursor.peek().is_some_and(is_identifier_char) {
+            *self = cursor;
+            return true;
+        }
+        false
+    }
+
+    /// A `"..."` string with escapes, or a single character `'.'`, like `'a'` or `'\n'`.
+    pub fn eat_char_or_string(&mut self) -> bool {
+        let mut cursor = *self;
+        let ok = match cursor.bump() {
+            Some('"') => loop {
+                match cursor.bump() {
+                    Some('\\') => {
+                        cursor.bump();
+                    }
+                    Some('"') => break true,
+                    Some(_) => {}
+                    None => break false,
+                }
+            },
+            Some('\'') => {
+                let char_ok = match cursor.bump() {
+                    Some('\\') => cursor.bump().is_some(),
+                    Some('\'') | None => false,
+                    Some(_) => true,
+                };
+                char_ok && cursor.bump() == Some('\'')
+            }
+            _ => false,
+        };
+
+        if ok {
+            *self = cursor;
+        }
+        ok
+    }
+
+    /// Skips a Rust token that quotes or brackets in it would be mistaken for: a comment, a string, raw string or
+    /// character literal, a lifetime, or an identifier, so that the `b` of `b"..."` isn't read as the end of a word.
+    ///
+    /// Returns false when none starts at the cursor, and an error when a literal or comment isn't closed.
+    pub fn eat_rust_token(&mut self) -> Result<bool, ParseError> {
+        if self.eat_line_comment() || self.eat_block_comment()? || self.eat_raw_string()? || self.eat_identifier().is_some() {
+            return Ok(true);
+        }
+
+        match self.peek() {
+            Some('"') => self.eat_string().map(|_| true),
+            Some('\'') => Ok(self.eat_char_or_lifetime()),
+            _ => Ok(false),
+        }
+    }
+
+    /// Reads Rust code up to `is_end`, or up to a closing bracket that wasn't opened in it.
+    ///
+    /// The brackets in between are balanced and the tokens that may hold them are skipped over, so the `{` of
+    /// `(Foo { a: 1 })` or `'{'` doesn't end the code.
+    pub fn eat_rust_until(&mut self, is_end: impl Fn(&Cursor) -> bool) -> Result<&'a str, ParseError> {
+        let start = *self;
+        let mut closing = Vec::new();
+
+        while let Some(c) = self.peek() {
+            if closing.is_empty() && is_end(self) {
+                break;
+            }
+            if self.eat_rust_token()? {
+                continue;
+            }
+
+            match c {
+                '(' => closing.push(')'),
+                '[' => closing.push(']'),
+                '{' => closing.push('}'),
+                ')' | ']' | '}' if closing.last() == Some(&c) => {
+                    closing.pop();
+                }
+                ')' | ']' | '}' => break,
+                _ => {}
+            }
+            self.bump();
+        }
+
+        Ok(self.since(&start))
+    }
+
+    fn eat_line_comment(&mut self) -> bool {
+        if !self.eat("//") {
+            return false;
+        }
+        while self.peek().is_some_and(|c| c != '\n') {
+            self.bump();
+        }
+        true
+    }
+
+    /// `/* ... */`, which may be nested.
+    fn eat_block_comment(&mut self) -> Result<bool, ParseError> {
+        let start = *self;
+        if !self.eat("/*") {
+            return Ok(false);
+        }
+
+        while !self.eat("*/") {
+            if !self.eat_block_comment()? && self.bump().is_none() {
+                return Err(start.error("unclosed block comment, expected `*/`").with_length(2));
+            }
+        }
+        Ok(true)
+    }
+
+    /// `"..."` with escapes.
+    fn eat_string(&mut self) -> Result<(), ParseError> {
+        let start = *self;
+        self.bump();
+
+        loop {
+            match self.bump() {
+                Some('\\') => {
+                    self.bump();
+                }
+                Some('"') => return Ok(()),
+                Some(_) => {}
+                None => return Err(start.error("unterminated string literal")),
+            }
+        }
+    }
+
+    /// `r"..."`, `r#"..."#` with any number of `#`, and their `br` and `cr` forms.
+    fn eat_raw_string(&mut self) -> Result<bool, ParseError> {
+        let start = *self;
+        let mut c = *self;
+        if !(c.eat("r") || c.eat("br") || c.eat("cr")) {
+            return Ok(false);
+        }
+
+        let hashes = c.rest().len() - c.rest().trim_start_matches('#').len();
+        let end = format!("\"{}", "#".repeat(hashes));
+        c.eat(&"#".repeat(hashes));
+        if !c.eat("\"") {
+            return Ok(false);
+        }
+
+        while !c.eat(&end) {
+            if c.bump().is_none() {
+                return Err(start.error("unterminated raw string literal"));
+            }
+        }
+
+        *self = c;
+        Ok(true)
+    }
+
+    /// A character like `'a'`, `'{'` or `'\u{7d}'`, or a lifetime or label like `'a` or `'outer`.
+    fn eat_char_or_lifetime(&mut self) -> bool {
+        let mut c = *self;
+        c.bump();
+
+        let ok = match c.bump() {
+            Some('\\') => {
+                c.bump();
+                while c.peek().is_some_and(|ch| ch != '\'' && ch != '\n') {
+                    c.bump();
+                }
+                c.eat("'")
+            }
+            Some(ch) if ch != '\n' && c.eat("'") => true,
+            Some(ch) if ch.is_ascii_alphabetic() || ch == '_' => {
+                while c.peek().is_some_and(is_identifier_char) {
+                    c.bump();
+                }
+                true
+            }
+            _ => false,
+        };
+
+        if ok {
+            *self = c;
+        }
+        ok
+    }
+
+    /// A string in double or single quotes, as the paths of directives are written.
+    pub fn eat_quoted(&mut self) -> Result<Option<&'a str>, ParseError> {
+        let start = *self;
+        let Some(quote @ ('"' | '\'')) = self.peek() else {
+            return Ok(None);
+        };
+        self.bump();
+
+        loop {
+            match self.bump() {
+                Some('\\') => {
+                    self.bump();
+                }
+                Some(c) if c == quote => return Ok(Some(self.since(&start))),
+                Some(_) => {}
+                None => return Err(start.error("unterminated string literal")),
+            }
+        }
+    }
+
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            message: message.into(),
+            offset: self.offset,
+            line: self.line,
+            column: self.column,
+            length: 1,
+        }
+    }
+}
+
+pub fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r')
+}
+
+pub fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
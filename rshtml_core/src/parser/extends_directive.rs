@@ -1,34 +1,41 @@
 ﻿use crate::Node;
-use crate::parser::{IParser, RsHtmlParser, Rule};
-use pest::error::{Error, ErrorVariant};
-use pest::iterators::Pair;
+use crate::error::ParseError;
+use crate::parser::cursor::Cursor;
+use crate::parser::{IParser, RsHtmlParser};
 use std::path::PathBuf;
 
 pub struct ExtendsDirectiveParser;
 
 impl IParser for ExtendsDirectiveParser {
-    fn parse(parser: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let pair_span = pair.as_span();
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let position = parser.position(cursor);
+
+        cursor.eat("@");
+        cursor.skip_whitespace();
+        cursor.eat("extends");
+        cursor.skip_whitespace();
 
         let mut path_str = parser.config.views.1.clone();
-        if let Some(path_pair) = pair.into_inner().find(|p| p.as_rule() == Rule::string_line) {
-            path_str = path_pair.as_str().trim_matches('"').trim_matches('\'').to_string();
+        if cursor.eat("(") {
+            cursor.skip_whitespace();
+            if let Some(path) = cursor.eat_quoted()? {
+                path_str = path.trim_matches('"').trim_matches('\'').to_string();
+            }
+            RsHtmlParser::parse_closing_parenthesis(cursor, "extends")?;
         }
 
-        let layout_node = match parser.parse_template(&path_str) {
-            Ok(node) => node,
-            Err(err) => {
-                let include_template_error = Error::new_from_span(
-                    ErrorVariant::CustomError {
-                        message: format!("Error parsing layout file '{}': {}", path_str, err),
-                    },
-                    pair_span,
-                );
-
-                return Err(Box::new(include_template_error));
-            }
-        };
+        let layout_node = parser.parse_included(&path_str, position);
 
         Ok(Node::ExtendsDirective(PathBuf::from(path_str), Box::new(layout_node)))
     }
 }
+
+impl ExtendsDirectiveParser {
+    pub fn starts(cursor: &Cursor) -> bool {
+        let mut c = *cursor;
+        c.eat("@") && {
+            c.skip_whitespace();
+            c.eat_keyword("extends")
+        }
+    }
+}
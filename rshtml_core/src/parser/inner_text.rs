@@ -1,13 +1,13 @@
 ﻿use crate::Node;
-use crate::parser::{IParser, RsHtmlParser, Rule};
-use pest::error::Error;
-use pest::iterators::Pair;
+use crate::error::ParseError;
+use crate::parser::cursor::Cursor;
+use crate::parser::text::TextParser;
+use crate::parser::{IParser, RsHtmlParser};
 
 pub struct InnerTextParser;
 
 impl IParser for InnerTextParser {
-    fn parse(_: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let inner_text = pair.as_str().replace("@@", "@").replace("@@{", "{").replace("@@}", "}");
-        Ok(Node::InnerText(inner_text))
+    fn parse(_: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        Ok(Node::InnerText(TextParser::scan(cursor, true)))
     }
 }
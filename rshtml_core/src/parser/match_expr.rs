@@ -1,72 +1,124 @@
 ﻿use crate::Node;
-use crate::parser::{IParser, RsHtmlParser, Rule};
-use pest::error::{Error, ErrorVariant};
-use pest::iterators::Pair;
+use crate::error::ParseError;
+use crate::parser::block::BlockParser;
+use crate::parser::cursor::Cursor;
+use crate::parser::rust_expr_paren::RustExprParenParser;
+use crate::parser::rust_expr_simple::RustExprSimpleParser;
+use crate::parser::text::TextParser;
+use crate::parser::{IParser, RsHtmlParser};
 
 pub struct MatchExprParser;
 
 impl IParser for MatchExprParser {
-    fn parse(parser: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let pair_span = pair.as_span();
-        let mut pairs = pair.into_inner();
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let position = parser.position(cursor);
+        let start = *cursor;
 
-        let match_expr_head = pairs.find(|pair| pair.as_rule() == Rule::match_expr_head).ok_or(Error::new_from_span(
-            ErrorVariant::CustomError {
-                message: "Match expression head cannot find.".to_string(),
-            },
-            pair_span,
-        ))?;
+        cursor.eat("match");
+        cursor.skip_whitespace();
+        if cursor.eat_rust_until(|c| c.starts_with("{"))?.is_empty() {
+            return Err(cursor.error("expected an expression after @match"));
+        }
+        let match_expr_head = cursor.since(&start).to_string();
 
-        let match_expr_arms = pairs.filter(|pair| pair.as_rule() == Rule::match_expr_arm);
+        if !cursor.eat("{") {
+            return Err(cursor.error("expected `{` to open the arms of @match"));
+        }
+        cursor.skip_whitespace();
 
         let mut nodes: Vec<(String, Vec<Node>)> = Vec::new();
-        for match_expr_arm in match_expr_arms {
-            let match_expr_arm_span = match_expr_arm.as_span();
-            let mut match_expr_arm_iter = match_expr_arm.into_inner();
-
-            let match_expr_arm_head = match_expr_arm_iter.next().ok_or(Error::new_from_span(
-                ErrorVariant::CustomError {
-                    message: "Match expression arm head cannot find.".to_string(),
-                },
-                match_expr_arm_span,
-            ))?;
-            if match_expr_arm_head.as_rule() != Rule::match_expr_arm_head {
-                return Err(Box::new(Error::new_from_span(
-                    ErrorVariant::CustomError {
-                        message: "Invalid match expression arm head.".to_string(),
-                    },
-                    match_expr_arm_span,
-                )));
-            }
+        while let Some(arm_head) = Self::eat_arm_head(cursor)? {
+            let node_arm_value = Self::parse_arm_value(parser, cursor, arm_head)?;
+            nodes.push((arm_head.to_string(), node_arm_value));
+            cursor.skip_whitespace();
+        }
 
-            let match_expr_arm_value = match_expr_arm_iter.next().ok_or(Error::new_from_span(
-                ErrorVariant::CustomError {
-                    message: "Match expression arm value cannot find.".to_string(),
-                },
-                match_expr_arm_span,
-            ))?;
-            let node_arm_value = match match_expr_arm_value.as_rule() {
-                Rule::inner_template => parser.build_nodes_from_pairs(match_expr_arm_value.into_inner())?,
-                Rule::continue_directive => vec![Node::ContinueDirective],
-                Rule::break_directive => vec![Node::BreakDirective],
-                Rule::rust_expr_paren => vec![parser.build_ast_node(match_expr_arm_value)?],
-                Rule::rust_expr_simple => vec![parser.build_ast_node(match_expr_arm_value)?],
-                Rule::match_inner_text => vec![Node::InnerText(
-                    match_expr_arm_value.as_str().replace("@@", "@").replace("@@{", "{").replace("@@}", "}"),
-                )],
-                _ => {
-                    return Err(Box::new(Error::new_from_span(
-                        ErrorVariant::CustomError {
-                            message: "Unexpected match expression arm value ".to_string(),
-                        },
-                        match_expr_arm_span,
-                    )));
-                }
-            };
+        if !cursor.eat("}") {
+            return Err(cursor.error("expected a match arm like `Some(x) => ...` or `}` to close @match"));
+        }
+
+        Ok(Node::MatchExpr(match_expr_head, nodes, position))
+    }
+}
+
+impl MatchExprParser {
+    pub fn starts(cursor: &Cursor) -> bool {
+        let mut c = *cursor;
+        c.eat("match") && c.peek().is_some_and(char::is_whitespace)
+    }
+
+    /// Reads the pattern of an arm and its `=>`. The pattern may hold braces and bindings, as in
+    /// `User { age: age @ 18.., .. } if age < 65`.
+    fn eat_arm_head<'a>(cursor: &mut Cursor<'a>) -> Result<Option<&'a str>, ParseError> {
+        let mut c = *cursor;
+        if c.starts_with("@") {
+            return Ok(None);
+        }
+        let head = c.eat_rust_until(|c| c.starts_with("=>"))?;
+        if head.is_empty() || !c.eat("=>") {
+            return Ok(None);
+        }
 
-            nodes.push((match_expr_arm_head.as_str().to_string(), node_arm_value));
+        *cursor = c;
+        Ok(Some(head))
+    }
+
+    /// Reads what an arm outputs: a `{ ... }` block, `continue`, `break`, an expression or text up to the end of the line.
+    fn parse_arm_value(parser: &mut RsHtmlParser, cursor: &mut Cursor, arm_head: &str) -> Result<Vec<Node>, ParseError> {
+        cursor.skip_whitespace();
+
+        let value = if cursor.eat("{") {
+            cursor.skip_whitespace();
+            parser.parse_block_body(cursor, &format!("the block of the `{}` arm", arm_head.trim()))?
+        } else if let Some(node) = BlockParser::eat_loop_directive(cursor) {
+            vec![node]
+        } else if RustExprParenParser::is_closed(cursor)? {
+            vec![RustExprParenParser::parse(parser, cursor)?]
+        } else if RustExprSimpleParser::starts(cursor) {
+            vec![RustExprSimpleParser::parse(parser, cursor)?]
+        } else if let Some(text) = Self::eat_arm_text(cursor) {
+            vec![Node::InnerText(text.replace("@@", "@"))]
+        } else {
+            return Err(cursor.error(format!("expected a value after `{} =>` in @match", arm_head.trim())));
+        };
+
+        cursor.skip_whitespace();
+        cursor.eat(",");
+
+        Ok(value)
+    }
+
+    /// Text up to the end of the line, an `@` that isn't text, or the `,` or `}` ending the arm.
+    fn eat_arm_text<'a>(cursor: &mut Cursor<'a>) -> Option<&'a str> {
+        let start = *cursor;
+
+        while let Some(c) = cursor.peek() {
+            match c {
+                '@' if cursor.starts_with("@@") => {
+                    cursor.eat("@@");
+                    continue;
+                }
+                '@' if TextParser::is_literal(cursor, &start) => {}
+                '@' | '\n' | '\r' | '}' => break,
+                ',' if Self::ends_arm_text(cursor) => break,
+                _ => {}
+            }
+            cursor.bump();
         }
 
-        Ok(Node::MatchExpr(match_expr_head.as_str().to_string(), nodes))
+        Some(cursor.since(&start)).filter(|text| !text.is_empty())
+    }
+
+    /// A `,` followed by the end of the line or by the `}` closing the match.
+    fn ends_arm_text(cursor: &Cursor) -> bool {
+        let mut line_end = *cursor;
+        line_end.eat(",");
+        while line_end.eat(" ") || line_end.eat("\t") {}
+
+        let mut block_end = *cursor;
+        block_end.eat(",");
+        block_end.skip_whitespace();
+
+        line_end.starts_with("\n") || line_end.starts_with("\r") || block_end.starts_with("}")
     }
 }
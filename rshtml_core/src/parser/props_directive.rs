@@ -0,0 +1,92 @@
+﻿use crate::Node;
+use crate::error::ParseError;
+use crate::node::ComponentProp;
+use crate::parser::cursor::Cursor;
+use crate::parser::{IParser, RsHtmlParser};
+
+pub struct PropsDirectiveParser;
+
+impl IParser for PropsDirectiveParser {
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let position = parser.position(cursor);
+
+        cursor.eat("props");
+        cursor.skip_whitespace();
+        cursor.eat("(");
+
+        let mut props = Vec::new();
+        loop {
+            cursor.skip_whitespace();
+            if cursor.starts_with(")") {
+                break;
+            }
+
+            let Some(name) = cursor.eat_identifier() else {
+                return Err(cursor.error("expected the name of a prop in @props"));
+            };
+            cursor.skip_whitespace();
+            if !cursor.eat(":") {
+                return Err(cursor.error(format!("expected `:` and the type of the prop `{}` in @props", name)));
+            }
+            cursor.skip_whitespace();
+
+            let ty = Self::eat_type(cursor)?.trim();
+            if ty.is_empty() {
+                return Err(cursor.error(format!("expected the type of the prop `{}` in @props", name)));
+            }
+
+            let default = if cursor.eat("=") {
+                let default = cursor.eat_rust_until(|c| c.starts_with(","))?.trim();
+                if default.is_empty() {
+                    return Err(cursor.error(format!("expected the default value of the prop `{}` in @props", name)));
+                }
+                Some(default.to_string())
+            } else {
+                None
+            };
+
+            props.push(ComponentProp {
+                name: name.to_string(),
+                ty: ty.to_string(),
+                default,
+            });
+
+            if !cursor.eat(",") {
+                break;
+            }
+        }
+
+        RsHtmlParser::parse_closing_parenthesis(cursor, "props")?;
+
+        Ok(Node::PropsDirective(props, position))
+    }
+}
+
+impl PropsDirectiveParser {
+    pub fn starts(cursor: &Cursor) -> bool {
+        RsHtmlParser::starts_call(cursor, "props")
+    }
+
+    /// Reads a type up to the `,`, `=` or `)` after it. Unlike in expressions, `<` and `>` are brackets in types,
+    /// so the `,` of `HashMap<K, V>` and the `=` of `impl Iterator<Item = u8>` belong to the type.
+    fn eat_type<'a>(cursor: &mut Cursor<'a>) -> Result<&'a str, ParseError> {
+        let start = *cursor;
+        let mut angles = 0;
+
+        loop {
+            cursor.eat_rust_until(|c| [",", "=", "<", ">", "-"].iter().any(|end| c.starts_with(end)))?;
+
+            if cursor.eat("-") {
+                cursor.eat(">");
+            } else if cursor.eat("<") {
+                angles += 1;
+            } else if angles > 0 && cursor.eat(">") {
+                angles -= 1;
+            } else if !(angles > 0 && (cursor.eat(",") || cursor.eat("="))) {
+                break;
+            }
+        }
+
+        Ok(cursor.since(&start))
+    }
+}
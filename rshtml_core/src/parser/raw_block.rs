@@ -1,25 +1,43 @@
 ﻿use crate::Node;
-use crate::parser::Rule::raw_content;
-use crate::parser::{IParser, RsHtmlParser, Rule};
-use pest::error::{Error, ErrorVariant};
-use pest::iterators::Pair;
+use crate::error::ParseError;
+use crate::parser::cursor::Cursor;
+use crate::parser::{IParser, RsHtmlParser};
 
 pub struct RawBlockParser;
 
 impl IParser for RawBlockParser {
-    fn parse(_: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let pair_span = pair.as_span();
-        Ok(Node::Raw(
-            pair.into_inner()
-                .find(|p| p.as_rule() == Rule::raw_content)
-                .map(|p| p.as_str().to_string())
-                .ok_or(Error::new_from_span(
-                    ErrorVariant::ParsingError {
-                        positives: vec![raw_content],
-                        negatives: vec![],
-                    },
-                    pair_span,
-                ))?,
-        ))
+    fn parse(_: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        cursor.eat("raw");
+        cursor.skip_whitespace();
+        let open = *cursor;
+        cursor.eat("{");
+        cursor.skip_whitespace();
+
+        let content = *cursor;
+        let mut depth = 0;
+        loop {
+            match cursor.peek() {
+                Some('{') => depth += 1,
+                Some('}') if depth == 0 => break,
+                Some('}') => depth -= 1,
+                Some(_) => {}
+                None => return Err(open.error("unclosed @raw block, expected `}`")),
+            }
+            cursor.bump();
+        }
+        let raw = cursor.since(&content).to_string();
+        cursor.eat("}");
+
+        Ok(Node::Raw(raw))
+    }
+}
+
+impl RawBlockParser {
+    pub fn starts(cursor: &Cursor) -> bool {
+        let mut c = *cursor;
+        c.eat("raw") && {
+            c.skip_whitespace();
+            c.starts_with("{")
+        }
     }
 }
@@ -1,94 +1,129 @@
 ﻿use crate::Node;
+use crate::error::ParseError;
 use crate::node::{RustBlockContent, TextBlockItem, TextLineItem};
-use crate::parser::{IParser, RsHtmlParser, Rule};
+use crate::parser::cursor::Cursor;
+use crate::parser::rust_expr_simple::RustExprSimpleParser;
+use crate::parser::text::TextParser;
+use crate::parser::{IParser, RsHtmlParser};
 use crate::traits::IsEscaped;
-use pest::error::{Error, ErrorVariant};
-use pest::iterators::{Pair, Pairs};
 
 pub struct RustBlockParser;
 
 impl IParser for RustBlockParser {
-    fn parse(_: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        Ok(Node::RustBlock(Self::build_rust_block_contents(pair.into_inner())?))
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let position = parser.position(cursor);
+        let open = *cursor;
+        cursor.eat("{");
+        Ok(Node::RustBlock(Self::build_rust_block_contents(cursor, &open)?, position))
     }
 }
 
 impl RustBlockParser {
-    fn build_rust_block_contents(pairs: Pairs<Rule>) -> Result<Vec<RustBlockContent>, Box<Error<Rule>>> {
+    pub fn starts(cursor: &Cursor) -> bool {
+        cursor.starts_with("{")
+    }
+
+    /// Reads the contents of a block whose `{` is at `open`, up to and including its `}`.
+    fn build_rust_block_contents(cursor: &mut Cursor, open: &Cursor) -> Result<Vec<RustBlockContent>, ParseError> {
         let mut content_parts = Vec::new();
-        for inner_pair in pairs {
-            match inner_pair.as_rule() {
-                Rule::text_line_directive => {
-                    content_parts.push(Self::build_text_line(inner_pair));
-                }
-                Rule::text_block_tag => {
-                    content_parts.push(Self::build_text_block(inner_pair));
-                }
-                Rule::rust_code => {
-                    content_parts.push(RustBlockContent::Code(inner_pair.as_str().to_string()));
-                }
-                Rule::nested_block => {
-                    let nested_contents = Self::build_rust_block_contents(inner_pair.into_inner())?;
-                    content_parts.push(RustBlockContent::NestedBlock(nested_contents));
-                }
-                rule => {
-                    return Err(Box::new(Error::new_from_span(
-                        ErrorVariant::CustomError {
-                            message: format!("Internal Error: Unexpected rule {:?} inside rust block content", rule),
-                        },
-                        inner_pair.as_span(),
-                    )));
-                }
+
+        loop {
+            cursor.skip_whitespace();
+
+            if cursor.eat("}") {
+                return Ok(content_parts);
+            } else if cursor.starts_with("@:") {
+                content_parts.push(Self::build_text_line(cursor));
+            } else if Self::text_block_starts(cursor) {
+                content_parts.push(Self::build_text_block(cursor));
+            } else if cursor.starts_with("{") {
+                let nested_open = *cursor;
+                cursor.eat("{");
+                let nested_contents = Self::build_rust_block_contents(cursor, &nested_open)?;
+                content_parts.push(RustBlockContent::NestedBlock(nested_contents));
+            } else if let Some(code) = Self::eat_code(cursor)? {
+                content_parts.push(RustBlockContent::Code(code.to_string()));
+            } else {
+                return Err(open.error("unclosed code block, expected `}`"));
             }
         }
-        Ok(content_parts)
     }
 
-    fn build_text_line(inner_pair: Pair<Rule>) -> RustBlockContent {
-        let mut items = Vec::new();
+    /// Rust code up to a `{`, `}`, `@:` or `<text>`, skipping over the strings, characters and comments that may hold them.
+    fn eat_code<'a>(cursor: &mut Cursor<'a>) -> Result<Option<&'a str>, ParseError> {
+        let start = *cursor;
 
-        for item_pair in inner_pair.into_inner() {
-            match item_pair.as_rule() {
-                Rule::rust_expr_simple => {
-                    let item_pair_str = item_pair.as_str();
-                    items.push(TextLineItem::RustExprSimple(item_pair_str.escaped_or_raw(), item_pair_str.is_escaped()));
-                }
-                Rule::text_line => {
-                    let text = item_pair.as_str().replace("@@", "@");
-                    if !text.is_empty() {
-                        items.push(TextLineItem::Text(text));
-                    }
-                }
-                _ => {
-                    eprintln!("Warning: Unexpected rule in text_block: {:?}", item_pair.as_rule());
-                }
+        while !cursor.is_eof() {
+            if cursor.eat_rust_token()? {
+                continue;
+            }
+            if ["@:", "{", "}"].iter().any(|end| cursor.starts_with(end)) || Self::text_block_starts(cursor) {
+                break;
             }
+            cursor.bump();
         }
 
-        RustBlockContent::TextLine(items)
+        Ok(Some(cursor.since(&start)).filter(|code| !code.is_empty()))
+    }
+
+    /// `@:` and text up to the end of the line, with `@expression`s in it.
+    fn build_text_line(cursor: &mut Cursor) -> RustBlockContent {
+        cursor.eat("@:");
+        let items = Self::build_text_items(cursor, |c| c.starts_with("\n") || c.starts_with("\r"));
+
+        RustBlockContent::TextLine(
+            items
+                .into_iter()
+                .map(|item| match item {
+                    TextBlockItem::Text(text) => TextLineItem::Text(text),
+                    TextBlockItem::RustExprSimple(expr, is_escaped) => TextLineItem::RustExprSimple(expr, is_escaped),
+                })
+                .collect(),
+        )
+    }
+
+    fn text_block_starts(cursor: &Cursor) -> bool {
+        cursor.starts_with("<text>") && cursor.rest().contains("</text>")
     }
 
-    fn build_text_block(inner_pair: Pair<Rule>) -> RustBlockContent {
+    /// `<text>...</text>`, with `@expression`s in it.
+    fn build_text_block(cursor: &mut Cursor) -> RustBlockContent {
+        cursor.eat("<text>");
+        let items = Self::build_text_items(cursor, |c| c.starts_with("</text>"));
+        cursor.eat("</text>");
+
+        RustBlockContent::TextBlock(items)
+    }
+
+    fn build_text_items(cursor: &mut Cursor, is_end: impl Fn(&Cursor) -> bool) -> Vec<TextBlockItem> {
         let mut items = Vec::new();
+        let mut text = *cursor;
+
+        let push_text = |items: &mut Vec<TextBlockItem>, text: &str| {
+            if !text.is_empty() {
+                items.push(TextBlockItem::Text(text.replace("@@", "@")));
+            }
+        };
+
+        while !cursor.is_eof() && !is_end(cursor) {
+            if cursor.eat("@@") {
+                continue;
+            }
 
-        for item_pair in inner_pair.into_inner() {
-            match item_pair.as_rule() {
-                Rule::rust_expr_simple => {
-                    let item_pair_str = item_pair.as_str();
-                    items.push(TextBlockItem::RustExprSimple(item_pair_str.escaped_or_raw(), item_pair_str.is_escaped()));
-                }
-                Rule::text_block => {
-                    let text = item_pair.as_str().replace("@@", "@");
-                    if !text.is_empty() {
-                        items.push(TextBlockItem::Text(text));
-                    }
-                }
-                _ => {
-                    eprintln!("Warning: Unexpected rule in text_block: {:?}", item_pair.as_rule());
-                }
+            let mut after = *cursor;
+            if after.eat("@") && RustExprSimpleParser::starts(&after) && !TextParser::is_literal(cursor, &text) {
+                push_text(&mut items, cursor.since(&text));
+                let expr = RustExprSimpleParser::scan(&mut after);
+                items.push(TextBlockItem::RustExprSimple(expr.escaped_or_raw(), expr.is_escaped()));
+                *cursor = after;
+                text = after;
+                continue;
             }
+
+            cursor.bump();
         }
 
-        RustBlockContent::TextBlock(items)
+        push_text(&mut items, cursor.since(&text));
+        items
     }
 }
@@ -1,62 +1,117 @@
 ﻿use crate::Node;
-use crate::parser::{IParser, RsHtmlParser, Rule};
-use pest::error::{Error, ErrorVariant};
-use pest::iterators::{Pair, Pairs};
-use std::iter::Peekable;
+use crate::error::ParseError;
+use crate::parser::cursor::Cursor;
+use crate::parser::{IParser, RsHtmlParser};
 
 pub struct RustExprParser;
 
 impl IParser for RustExprParser {
-    fn parse(parser: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let pair_span = pair.as_span();
-
-        let mut inner_pairs = pair.into_inner().peekable();
-        let mut clauses: Vec<(String, Vec<Node>)> = Vec::new();
-
-        let consume_whitespaces = |inner_p: &mut Peekable<Pairs<Rule>>| {
-            while let Some(p) = inner_p.peek() {
-                if p.as_rule() == Rule::WHITESPACE {
-                    inner_p.next();
-                } else {
-                    break;
-                }
-            }
-        };
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let position = parser.position(cursor);
 
-        while inner_pairs.peek().is_some() {
-            let head_pair = inner_pairs.next_if(|p| p.as_rule() == Rule::rust_expr_head).ok_or(Error::new_from_span(
-                ErrorVariant::CustomError {
-                    message: format!("Internal Error: rust_expr expected a head, found {:?}", inner_pairs.peek().map(|p| p.as_rule())),
-                },
-                pair_span,
-            ))?;
-            let head = head_pair.as_str().trim().to_string();
+        let mut clauses: Vec<(String, Vec<Node>)> = vec![Self::parse_clause(parser, cursor)?];
 
-            consume_whitespaces(&mut inner_pairs);
+        // the whitespace after the first clause is never output, the whitespace after the others is
+        cursor.skip_whitespace();
+        let mut next = *cursor;
+        while Self::clause_starts(&next) {
+            *cursor = next;
+            clauses.push(Self::parse_clause(parser, cursor)?);
 
-            let template_pair = inner_pairs.next_if(|p| p.as_rule() == Rule::inner_template).ok_or(Error::new_from_span(
-                ErrorVariant::CustomError {
-                    message: format!("Internal Error: rust_expr missing inner_template for head: '{}'", head),
-                },
-                pair_span,
-            ))?;
+            next = *cursor;
+            next.skip_whitespace();
+        }
 
-            let body_nodes = parser.build_nodes_from_pairs(template_pair.into_inner())?;
+        Ok(Node::RustExpr(clauses, position))
+    }
+}
 
-            clauses.push((head.clone(), body_nodes));
+impl RustExprParser {
+    /// `if`, `for` or `while` and whitespace, or `else` and a `{` or `if`.
+    pub fn starts(cursor: &Cursor) -> bool {
+        let mut c = *cursor;
+        (["if", "for", "while"].iter().any(|keyword| c.eat(keyword)) && c.peek().is_some_and(char::is_whitespace)) || Self::else_starts(cursor)
+    }
 
-            consume_whitespaces(&mut inner_pairs);
+    fn else_starts(cursor: &Cursor) -> bool {
+        let mut c = *cursor;
+        c.eat("else") && {
+            let mut block = c;
+            block.skip_whitespace();
+            block.starts_with("{") || (c.eat_whitespace() && c.eat("if") && c.eat_whitespace())
         }
+    }
 
-        if clauses.is_empty() {
-            return Err(Box::new(Error::new_from_span(
-                ErrorVariant::CustomError {
-                    message: "Internal Error: rust_expr parsed with no clauses".to_string(),
-                },
-                pair_span,
-            )));
+    /// Whether another clause follows, like the `else` of an `if`. Unlike the first one, it needs its `{`.
+    fn clause_starts(cursor: &Cursor) -> bool {
+        let mut c = *cursor;
+        Self::eat_head(&mut c).is_ok() && {
+            c.skip_whitespace();
+            c.starts_with("{")
+        }
+    }
+
+    /// Reads the head of a clause, like `if a > b` or `else`, up to its `{`.
+    fn eat_head<'a>(cursor: &mut Cursor<'a>) -> Result<&'a str, ParseError> {
+        let start = *cursor;
+
+        if cursor.eat("else") {
+            let mut c = *cursor;
+            if !(c.eat_whitespace() && c.eat("if") && c.eat_whitespace()) {
+                return Ok("else");
+            }
+            *cursor = c;
+        } else if !(["if", "for", "while"].iter().any(|keyword| cursor.eat(keyword)) && cursor.eat_whitespace()) {
+            return Err(start.error("expected `if`, `else`, `for` or `while`"));
+        }
+
+        let condition = *cursor;
+        // a pattern may hold braces, like `for Point { x, y } in points` or `if let Point { x, y } = point`
+        if start.starts_with("for") {
+            Self::eat_pattern(cursor, |c| { *c }.eat_keyword("in"), "in")?;
+        }
+        loop {
+            cursor.eat_rust_until(|c| c.starts_with("{") || { *c }.eat_keyword("let"))?;
+            if !cursor.eat_keyword("let") {
+                break;
+            }
+            Self::eat_pattern(cursor, Self::at_let_equals, "=")?;
         }
 
-        Ok(Node::RustExpr(clauses))
+        if cursor.offset() == condition.offset() {
+            return Err(condition.error(format!("expected a condition after @{}", cursor.since(&start).trim())));
+        }
+
+        Ok(cursor.since(&start).trim())
+    }
+
+    /// Reads a pattern up to `is_end` and the `end` token after it. Leaves the cursor where it was when there isn't one.
+    fn eat_pattern(cursor: &mut Cursor, is_end: impl Fn(&Cursor) -> bool, end: &str) -> Result<(), ParseError> {
+        let mut c = *cursor;
+        c.eat_rust_until(is_end)?;
+        if c.eat(end) {
+            *cursor = c;
+        }
+
+        Ok(())
+    }
+
+    /// The `=` of a `let`, not part of `==`, `=>`, `..=` or another operator.
+    fn at_let_equals(cursor: &Cursor) -> bool {
+        cursor.starts_with("=") && !cursor.starts_with("==") && !cursor.starts_with("=>") && !cursor.before().ends_with(['=', '!', '<', '>', '.'])
+    }
+
+    fn parse_clause(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<(String, Vec<Node>), ParseError> {
+        let head = Self::eat_head(cursor)?.to_string();
+
+        cursor.skip_whitespace();
+        if !cursor.eat("{") {
+            return Err(cursor.error(format!("expected `{{` to open the @{} block", head)));
+        }
+        cursor.skip_whitespace();
+
+        let body_nodes = parser.parse_block_body(cursor, &format!("the @{} block", head))?;
+
+        Ok((head, body_nodes))
     }
 }
@@ -1,14 +1,89 @@
+use crate::error::ParseError;
 use crate::node::Node;
-use crate::parser::{IParser, RsHtmlParser, Rule};
+use crate::parser::cursor::Cursor;
+use crate::parser::{IParser, RsHtmlParser};
 use crate::traits::IsEscaped;
-use pest::error::Error;
-use pest::iterators::Pair;
+use std::collections::HashSet;
 
 pub struct RustExprParenParser;
 
 impl IParser for RustExprParenParser {
-    fn parse(_: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let pair_str = pair.as_str();
-        Ok(Node::RustExprParen(pair_str.escaped_or_raw(), pair_str.is_escaped()))
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let position = parser.position(cursor);
+        let open = *cursor;
+        let expr = Self::scan(cursor)?.ok_or_else(|| {
+            let mut open = open;
+            open.eat("#");
+            open.error("unclosed `(` in expression, expected `)`")
+        })?;
+
+        Ok(Node::RustExprParen(expr.escaped_or_raw(), expr.is_escaped(), position))
+    }
+}
+
+impl RustExprParenParser {
+    pub fn starts(cursor: &Cursor) -> bool {
+        cursor.starts_with("(") || cursor.starts_with("#(")
+    }
+
+    pub fn is_closed(cursor: &Cursor) -> Result<bool, ParseError> {
+        Ok(Self::scan(&mut { *cursor })?.is_some())
+    }
+
+    /// Reads `(...)` or `#(...)` up to the matching parenthesis. Brackets and braces inside are matched too, except in
+    /// strings, characters and comments. Returns `None` when the parenthesis isn't closed, and an error when a string
+    /// or comment in it isn't.
+    pub fn scan<'a>(cursor: &mut Cursor<'a>) -> Result<Option<&'a str>, ParseError> {
+        let mut c = *cursor;
+        c.eat("#");
+        if !c.eat("(") || !Self::scan_group(&mut c, ')', &mut HashSet::new())? {
+            return Ok(None);
+        }
+
+        let expr = c.since(cursor);
+        *cursor = c;
+        Ok(Some(expr))
+    }
+
+    /// Reads up to `close`. A bracket that isn't closed before it is read as any other character.
+    ///
+    /// The brackets found unclosed are kept in `unclosed` by offset, so they are only scanned once.
+    fn scan_group(cursor: &mut Cursor, close: char, unclosed: &mut HashSet<(usize, char)>) -> Result<bool, ParseError> {
+        loop {
+            let Some(c) = cursor.peek() else {
+                return Ok(false);
+            };
+
+            if c == close {
+                cursor.bump();
+                return Ok(true);
+            }
+
+            if cursor.eat_rust_token()? {
+                continue;
+            }
+
+            let nested_close = match c {
+                '(' => Some(')'),
+                '[' => Some(']'),
+                '{' => Some('}'),
+                _ => None,
+            };
+
+            if let Some(nested_close) = nested_close {
+                let mut nested = *cursor;
+                nested.bump();
+                // an error past the end of a group that isn't closed is reported if the scan gets there without it
+                if !unclosed.contains(&(nested.offset(), nested_close)) {
+                    if let Ok(true) = Self::scan_group(&mut nested, nested_close, unclosed) {
+                        *cursor = nested;
+                        continue;
+                    }
+                    unclosed.insert((cursor.offset() + 1, nested_close));
+                }
+            }
+
+            cursor.bump();
+        }
     }
 }
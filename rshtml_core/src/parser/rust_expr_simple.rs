@@ -1,14 +1,122 @@
+use crate::error::ParseError;
 use crate::node::Node;
-use crate::parser::{IParser, RsHtmlParser, Rule};
+use crate::parser::cursor::{Cursor, is_whitespace};
+use crate::parser::{IParser, RsHtmlParser};
 use crate::traits::IsEscaped;
-use pest::error::Error;
-use pest::iterators::Pair;
+
+/// Words that start directives and statements rather than expressions when followed by whitespace.
+const KEYWORDS: [&str; 13] = [
+    "{",
+    "if",
+    "for",
+    "while",
+    "else",
+    "match",
+    "include",
+    "extends",
+    "render",
+    "section",
+    "render_body",
+    "raw",
+    "use",
+];
 
 pub struct RustExprSimpleParser;
 
 impl IParser for RustExprSimpleParser {
-    fn parse(_: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let pair_str = pair.as_str();
-        Ok(Node::RustExprSimple(pair_str.escaped_or_raw(), pair_str.is_escaped()))
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let position = parser.position(cursor);
+        let expr = Self::scan(cursor);
+        Ok(Node::RustExprSimple(expr.escaped_or_raw(), expr.is_escaped(), position))
+    }
+}
+
+impl RustExprSimpleParser {
+    /// `ident`, `#ident` or `&ident`, not a keyword followed by whitespace.
+    pub fn starts(cursor: &Cursor) -> bool {
+        let mut c = *cursor;
+
+        if let Some(keyword) = KEYWORDS.iter().find(|keyword| c.starts_with(keyword)) {
+            let mut after = c;
+            after.eat(keyword);
+            if after.peek().is_some_and(is_whitespace) {
+                return false;
+            }
+        }
+
+        c.eat("#");
+        while c.eat("&") {}
+        c.eat_identifier().is_some()
+    }
+
+    /// Reads an expression like `self.user.name`, `items[0]` or `#format(x)`, which `starts` at the cursor.
+    ///
+    /// Chained calls and indexes may hold anything but a newline, an `@`, a `{` or an html tag, so the expression
+    /// doesn't run into the text after it.
+    pub fn scan<'a>(cursor: &mut Cursor<'a>) -> &'a str {
+        let start = *cursor;
+
+        cursor.eat("#");
+        while cursor.eat("&") {}
+        cursor.eat_identifier();
+
+        loop {
+            let mut c = *cursor;
+            let chained = match c.peek() {
+                Some('&') | Some('.') => {
+                    c.bump();
+                    c.eat_identifier().is_some()
+                }
+                Some(':') => c.eat("::") && c.eat_identifier().is_some(),
+                Some('(') => {
+                    c.bump();
+                    Self::scan_group(&mut c, ')')
+                }
+                Some('[') => {
+                    c.bump();
+                    Self::scan_group(&mut c, ']')
+                }
+                _ => false,
+            };
+
+            if !chained {
+                break;
+            }
+            *cursor = c;
+        }
+
+        cursor.since(&start)
+    }
+
+    /// Reads the inside of a call or index up to `close`, returns false when it runs into the end of the expression.
+    fn scan_group(cursor: &mut Cursor, close: char) -> bool {
+        loop {
+            let Some(c) = cursor.peek() else {
+                return false;
+            };
+
+            match c {
+                c if c == close => {
+                    cursor.bump();
+                    return true;
+                }
+                '(' | '[' => {
+                    // an opening bracket that isn't closed is read as any other character
+                    let mut nested = *cursor;
+                    nested.bump();
+                    if Self::scan_group(&mut nested, if c == '(' { ')' } else { ']' }) {
+                        *cursor = nested;
+                    } else {
+                        cursor.bump();
+                    }
+                }
+                '"' | '\'' if cursor.eat_char_or_string() => {}
+                ')' | ']' | '@' | '{' | '\n' | '\r' => return false,
+                '<' if cursor.peek_nth(1).is_some_and(|c| c == '/' || c.is_ascii_alphabetic()) => return false,
+                _ => {
+                    cursor.bump();
+                }
+            }
+        }
     }
 }
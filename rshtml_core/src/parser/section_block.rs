@@ -1,48 +1,30 @@
 ﻿use crate::Node;
-use crate::parser::{IParser, RsHtmlParser, Rule};
-use pest::error::{Error, ErrorVariant};
-use pest::iterators::Pair;
+use crate::error::ParseError;
+use crate::parser::cursor::Cursor;
+use crate::parser::{IParser, RsHtmlParser};
 
 pub struct SectionBlockParser;
 
 impl IParser for SectionBlockParser {
-    fn parse(parser: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let pair_span = pair.as_span();
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        cursor.eat("section");
+        cursor.skip_whitespace();
+        let section_head = cursor.eat_identifier().unwrap_or_default().to_string();
 
-        let section_head_pair = pair
-            .clone()
-            .into_inner()
-            .find(|p| p.as_rule() == Rule::section_head)
-            .ok_or(Error::new_from_span(
-                ErrorVariant::ParsingError {
-                    positives: vec![Rule::section_head],
-                    negatives: vec![],
-                },
-                pair_span,
-            ))?;
+        cursor.skip_whitespace();
+        if !cursor.eat("{") {
+            return Err(cursor.error(format!("expected `{{` to open @section {}", section_head)));
+        }
+        cursor.skip_whitespace();
 
-        let section_name_pair = section_head_pair
-            .into_inner()
-            .find(|p| p.as_rule() == Rule::rust_identifier)
-            .ok_or(Error::new_from_span(
-                ErrorVariant::ParsingError {
-                    positives: vec![Rule::rust_identifier],
-                    negatives: vec![],
-                },
-                pair_span,
-            ))?;
-
-        let section_head = section_name_pair.as_str().trim_matches('"').trim_matches('\'').to_string();
-
-        let inner_pairs = pair.into_inner().find(|x| x.as_rule() == Rule::inner_template).ok_or(Error::new_from_span(
-            ErrorVariant::ParsingError {
-                positives: vec![Rule::inner_template],
-                negatives: vec![],
-            },
-            pair_span,
-        ))?;
-
-        let body = parser.build_nodes_from_pairs(inner_pairs.into_inner())?;
+        let body = parser.parse_block_body(cursor, &format!("@section {}", section_head))?;
         Ok(Node::SectionBlock(section_head, body))
     }
 }
+
+impl SectionBlockParser {
+    pub fn starts(cursor: &Cursor) -> bool {
+        let mut c = *cursor;
+        c.eat("section") && c.eat_whitespace() && c.eat_identifier().is_some()
+    }
+}
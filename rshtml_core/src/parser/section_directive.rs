@@ -1,44 +1,41 @@
 ﻿use crate::Node;
+use crate::error::ParseError;
 use crate::node::SectionDirectiveContent;
-use crate::parser::{IParser, RsHtmlParser, Rule};
+use crate::parser::cursor::Cursor;
+use crate::parser::rust_expr_simple::RustExprSimpleParser;
+use crate::parser::{IParser, RsHtmlParser};
 use crate::traits::IsEscaped;
-use pest::error::{Error, ErrorVariant};
-use pest::iterators::Pair;
 
 pub struct SectionDirectiveParser;
 
 impl IParser for SectionDirectiveParser {
-    fn parse(_: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let pair_span = pair.as_span();
-        let mut pairs = pair
-            .clone()
-            .into_inner()
-            .filter(|p| p.as_rule() == Rule::string_line || p.as_rule() == Rule::rust_expr_simple);
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let name = RsHtmlParser::parse_string_argument_start(cursor, "section", "the name of the section")?;
 
-        match (pairs.next(), pairs.next()) {
-            (Some(name), Some(value)) => {
-                let value_pair = match value.as_rule() {
-                    Rule::string_line => {
-                        let value = value.as_str().trim_matches('"').trim_matches('\'').to_string();
-                        SectionDirectiveContent::Text(value)
-                    }
-                    Rule::rust_expr_simple => {
-                        let value = value.as_str();
-                        SectionDirectiveContent::RustExprSimple(value.escaped_or_raw(), value.is_escaped())
-                    }
-                    _ => unreachable!(),
-                };
+        cursor.skip_whitespace();
+        if !cursor.eat(",") {
+            return Err(cursor.error("expected `,` and the content of the section in @section"));
+        }
+        cursor.skip_whitespace();
 
-                let name = name.as_str().trim_matches('"').trim_matches('\'').to_string();
+        let value = if let Some(text) = cursor.eat_quoted()? {
+            SectionDirectiveContent::Text(text.trim_matches('"').trim_matches('\'').to_string())
+        } else if RustExprSimpleParser::starts(cursor) {
+            let position = parser.position(cursor);
+            let value = RustExprSimpleParser::scan(cursor);
+            SectionDirectiveContent::RustExprSimple(value.escaped_or_raw(), value.is_escaped(), position)
+        } else {
+            return Err(cursor.error("expected a string or an expression as the content of the section in @section"));
+        };
 
-                Ok(Node::SectionDirective(name, value_pair))
-            }
-            _ => Err(Box::new(Error::new_from_span(
-                ErrorVariant::CustomError {
-                    message: "Error: section_directive".to_string(),
-                },
-                pair_span,
-            ))),
-        }
+        RsHtmlParser::parse_closing_parenthesis(cursor, "section")?;
+
+        Ok(Node::SectionDirective(name, value))
+    }
+}
+
+impl SectionDirectiveParser {
+    pub fn starts(cursor: &Cursor) -> bool {
+        RsHtmlParser::starts_call(cursor, "section")
     }
 }
@@ -0,0 +1,28 @@
+﻿use crate::Node;
+use crate::error::ParseError;
+use crate::parser::cursor::Cursor;
+use crate::parser::{IParser, RsHtmlParser};
+
+pub struct SlotDirectiveParser;
+
+impl IParser for SlotDirectiveParser {
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let position = parser.position(cursor);
+        let start = *cursor;
+        let name = RsHtmlParser::parse_string_argument(cursor, "slot", "the name of a slot")?;
+
+        // a slot is named like in `<slot:name>`, the name is part of a parameter of the component's function
+        if Cursor::new(&name).eat_identifier() != Some(name.as_str()) {
+            let message = format!("the name of a slot is an identifier like `main_header`, got \"{}\" in @slot", name);
+            return Err(start.error(message).with_length(cursor.since(&start).len()));
+        }
+
+        Ok(Node::SlotDirective(name, position))
+    }
+}
+
+impl SlotDirectiveParser {
+    pub fn starts(cursor: &Cursor) -> bool {
+        RsHtmlParser::starts_call(cursor, "slot")
+    }
+}
@@ -0,0 +1,50 @@
+﻿use crate::Node;
+use crate::error::ParseError;
+use crate::parser::cursor::Cursor;
+use crate::parser::{Content, IParser, RsHtmlParser};
+
+pub struct SlotTagParser;
+
+impl IParser for SlotTagParser {
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let position = parser.position(cursor);
+        let open = *cursor;
+        cursor.eat("<slot:");
+
+        let Some(name) = cursor.eat_identifier() else {
+            return Err(cursor.error("expected the name of the slot after `<slot:`"));
+        };
+        cursor.skip_whitespace();
+        if !cursor.eat(">") {
+            return Err(cursor.error(format!("expected `>` to end the <slot:{}> tag", name)));
+        }
+
+        let body = parser.parse_nodes(cursor, Content::Template);
+
+        let close = *cursor;
+        let closing_name = cursor.eat("</slot:").then(|| cursor.eat_identifier()).flatten();
+        if closing_name != Some(name) || !cursor.eat(">") {
+            return Err(close.error(format!(
+                "expected `</slot:{}>` to close the <slot:{}> tag opened at {}:{}",
+                name,
+                name,
+                open.line(),
+                open.column()
+            )));
+        }
+
+        Ok(Node::Slot(name.to_string(), body, position))
+    }
+}
+
+impl SlotTagParser {
+    /// `<slot:`, as in `<slot:header>`.
+    pub fn starts(cursor: &Cursor) -> bool {
+        cursor.starts_with("<slot:")
+    }
+
+    /// `</slot:`, as in `</slot:header>`.
+    pub fn closes(cursor: &Cursor) -> bool {
+        cursor.starts_with("</slot:")
+    }
+}
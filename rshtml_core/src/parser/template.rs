@@ -1,12 +1,45 @@
+use crate::diagnostic::Diagnostic;
+use crate::error::ParseError;
 use crate::node::Node;
-use crate::parser::{IParser, RsHtmlParser, Rule};
-use pest::error::Error;
-use pest::iterators::Pair;
+use crate::parser::cursor::Cursor;
+use crate::parser::extends_directive::ExtendsDirectiveParser;
+use crate::parser::{Content, IParser, RsHtmlParser};
 
 pub struct TemplateParser;
 
 impl IParser for TemplateParser {
-    fn parse(parser: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        Ok(Node::Template(parser.build_nodes_from_pairs(pair.into_inner())?))
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let mut nodes = Vec::new();
+
+        // whitespace before the content and around the byte order mark and `@extends` isn't output
+        cursor.skip_whitespace();
+        cursor.eat("\u{FEFF}");
+        cursor.skip_whitespace();
+        if ExtendsDirectiveParser::starts(cursor) {
+            let start = *cursor;
+            match ExtendsDirectiveParser::parse(parser, cursor) {
+                Ok(node) => nodes.push(node),
+                Err(err) => parser.recover(cursor, &start, err),
+            }
+            cursor.skip_whitespace();
+        }
+
+        nodes.extend(parser.parse_nodes(cursor, Content::Template));
+
+        // a closing tag without an opening tag ends the content early, it is reported and the rest is parsed
+        while !cursor.is_eof() {
+            let mut tag = *cursor;
+            while tag.peek().is_some_and(|c| c != '>' && !c.is_whitespace()) {
+                tag.bump();
+            }
+            let err = cursor.error(format!("unexpected closing tag `{}>` without an opening tag", tag.since(cursor)));
+            parser.report(Diagnostic::from_parse_error(err.with_length(tag.since(cursor).len() + 1), &parser.file));
+
+            tag.eat(">");
+            *cursor = tag;
+            nodes.extend(parser.parse_nodes(cursor, Content::Template));
+        }
+
+        Ok(Node::Template(nodes))
     }
 }
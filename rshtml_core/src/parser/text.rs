@@ -1,13 +1,80 @@
 ﻿use crate::Node;
-use crate::parser::{IParser, RsHtmlParser, Rule};
-use pest::error::Error;
-use pest::iterators::Pair;
+use crate::error::ParseError;
+use crate::parser::component_tag::ComponentTagParser;
+use crate::parser::cursor::{Cursor, is_identifier_char};
+use crate::parser::slot_tag::SlotTagParser;
+use crate::parser::{IParser, RsHtmlParser};
+
+/// At-rules whose `@` is written as is in a `<style>` element.
+const CSS_AT_RULES: [&str; 16] = [
+    "charset",
+    "container",
+    "counter-style",
+    "font-face",
+    "font-feature-values",
+    "font-palette-values",
+    "import",
+    "keyframes",
+    "layer",
+    "media",
+    "namespace",
+    "page",
+    "property",
+    "scope",
+    "starting-style",
+    "supports",
+];
 
 pub struct TextParser;
 
 impl IParser for TextParser {
-    fn parse(_: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let text = pair.as_str().replace("@@", "@").replace("@@{", "{").replace("@@}", "}");
-        Ok(Node::Text(text))
+    fn parse(_: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        Ok(Node::Text(Self::scan(cursor, false)))
+    }
+}
+
+impl TextParser {
+    /// Reads text up to the next `@`, component or slot tag, and `}` in a block. `@@` is an escaped `@`, and an `@` that
+    /// [`is_literal`](Self::is_literal) is text too.
+    pub fn scan(cursor: &mut Cursor, in_block: bool) -> String {
+        let start = *cursor;
+
+        while let Some(c) = cursor.peek() {
+            match c {
+                '@' if cursor.starts_with("@@") => {
+                    cursor.eat("@@");
+                    continue;
+                }
+                '@' if !Self::is_literal(cursor, &start) => break,
+                '}' if in_block => break,
+                '<' if ComponentTagParser::starts(cursor) || ComponentTagParser::closes(cursor) => break,
+                '<' if SlotTagParser::starts(cursor) || SlotTagParser::closes(cursor) => break,
+                _ => {}
+            }
+            cursor.bump();
+        }
+
+        cursor.since(&start).replace("@@", "@")
+    }
+
+    /// Whether the `@` at the cursor is text rather than the start of an expression or directive, like Razor does.
+    ///
+    /// It is when it follows a letter or digit of the text that started at `text`, as in `support@example.com`, unless
+    /// it opens a parenthesized expression, as in `total@(price)`. The `@` of a CSS at-rule like `@media` in a `<style>`
+    /// element is text too.
+    pub fn is_literal(cursor: &Cursor, text: &Cursor) -> bool {
+        let mut after = *cursor;
+        after.eat("@");
+
+        let in_word = cursor.since(text).chars().next_back().is_some_and(char::is_alphanumeric);
+        if in_word && !(after.starts_with("(") || after.starts_with("#(")) {
+            return true;
+        }
+
+        let is_at_rule = CSS_AT_RULES.iter().any(|rule| {
+            let mut c = after;
+            c.eat(rule) && !c.peek().is_some_and(|c| is_identifier_char(c) || c == '-')
+        });
+        is_at_rule && cursor.in_style()
     }
 }
@@ -1,69 +1,90 @@
 ﻿use crate::Node;
-use crate::parser::{IParser, RsHtmlParser, Rule};
-use pest::error::{Error, ErrorVariant};
-use pest::iterators::Pair;
+use crate::error::ParseError;
+use crate::parser::cursor::{Cursor, is_whitespace};
+use crate::parser::{IParser, RsHtmlParser};
 use std::path::Path;
 
 pub struct UseDirectiveParser;
 
 impl IParser for UseDirectiveParser {
-    fn parse(parser: &mut RsHtmlParser, pair: Pair<Rule>) -> Result<Node, Box<Error<Rule>>> {
-        let pair_span = pair.as_span();
+    fn parse(parser: &mut RsHtmlParser, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let position = parser.position(cursor);
 
-        let mut inner_pairs = pair.into_inner();
-        let import_path_str = inner_pairs.find(|p| p.as_rule() == Rule::string_line).ok_or(Error::new_from_span(
-            ErrorVariant::ParsingError {
-                positives: vec![Rule::string_line],
-                negatives: vec![],
-            },
-            pair_span,
-        ))?;
+        cursor.eat("use");
+        cursor.skip_whitespace();
+        let inline = cursor.eat_keyword("inline");
+        cursor.skip_whitespace();
+        let path_start = *cursor;
+        let Some(import_path_str) = cursor.eat_quoted()? else {
+            return Err(cursor.error("expected the path of a component in quotes in @use"));
+        };
+        let path_length = cursor.offset() - path_start.offset();
+        cursor.skip_whitespace();
 
-        let import_path_str = import_path_str.as_str().trim_matches('"').to_string();
+        let import_path_str = import_path_str.trim_matches('"').to_string();
         let import_path = Path::new(&import_path_str);
         import_path
             .file_name()
             .and_then(|name| name.to_str())
             .filter(|name| name.ends_with(".rs.html"))
-            .ok_or(Error::new_from_span(
-                ErrorVariant::CustomError {
-                    message: format!(
+            .ok_or(
+                path_start
+                    .error(format!(
                         "Failed to derive component name from import path extension: '{:#?}'. Expected format like 'name.rs.html'.",
                         import_path
-                    ),
-                },
-                pair_span,
-            ))?;
+                    ))
+                    .with_length(path_length),
+            )?;
 
-        let component_name = match inner_pairs.find(|p| p.as_rule() == Rule::rust_identifier) {
-            Some(component_name_pair) => component_name_pair.as_str().to_string(),
+        let component_name = match Self::eat_alias(cursor) {
+            Some(component_name) => component_name.to_string(),
             None => import_path
                 .file_stem()
                 .and_then(|stem1| Path::new(stem1).file_stem())
                 .and_then(|stem2| stem2.to_str())
                 .map(|s| s.to_string())
-                .ok_or(Error::new_from_span(
-                    ErrorVariant::CustomError {
-                        message: format!("Failed to derive component name from import path: '{:#?}'", import_path),
-                    },
-                    pair_span,
-                ))?,
+                .ok_or(
+                    path_start
+                        .error(format!("Failed to derive component name from import path: '{:#?}'", import_path))
+                        .with_length(path_length),
+                )?,
         };
 
-        let component_node = match parser.parse_template(&import_path_str) {
-            Ok(node) => node,
-            Err(err) => {
-                let include_template_error = Error::new_from_span(
-                    ErrorVariant::CustomError {
-                        message: format!("Error parsing component file '{}': {}", import_path_str, err),
-                    },
-                    pair_span,
-                );
+        let component_node = parser.parse_included(&import_path_str, position);
+
+        Ok(Node::UseDirective(
+            component_name.clone(),
+            import_path.to_path_buf(),
+            Box::new(component_node),
+            inline,
+        ))
+    }
+}
 
-                return Err(Box::new(include_template_error));
+impl UseDirectiveParser {
+    pub fn starts(cursor: &Cursor) -> bool {
+        let mut c = *cursor;
+        c.eat("use") && {
+            c.skip_whitespace();
+            if c.eat_keyword("inline") {
+                c.skip_whitespace();
             }
-        };
+            c.starts_with("\"") || c.starts_with("'")
+        }
+    }
+
+    /// `as Name`, ended by `;` or whitespace.
+    fn eat_alias<'a>(cursor: &mut Cursor<'a>) -> Option<&'a str> {
+        let mut c = *cursor;
+        if !(c.eat("as") && c.eat_whitespace()) {
+            return None;
+        }
+        let name = c.eat_identifier()?;
+        if !(c.eat(";") || c.is_eof() || c.peek().is_some_and(is_whitespace)) {
+            return None;
+        }
 
-        Ok(Node::UseDirective(component_name.clone(), import_path.to_path_buf(), Box::new(component_node)))
+        *cursor = c;
+        Some(name)
     }
 }
@@ -0,0 +1,162 @@
+use crate::traits::RsHtmlMut;
+use bytes::Bytes;
+use futures_channel::mpsc::{Receiver, Sender, channel};
+use futures_core::Stream;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::Thread;
+
+/// How many chunks the render thread gets ahead of the reader before it waits.
+const BUFFERED_CHUNKS: usize = 1;
+
+/// How many threads render streams at most. Further streams wait for one of them to finish.
+const MAX_RENDER_THREADS: usize = 64;
+
+/// The chunks of a template rendered with [`RsHtml::render_stream`](crate::traits::RsHtml::render_stream).
+///
+/// The template is rendered on a pool of up to [`MAX_RENDER_THREADS`] threads, and waits at a `@flush` until the
+/// reader has taken the chunks before, so a slow client doesn't make the whole page pile up in memory. A render waits
+/// for a thread while they are all taken, so read the streams started before it rather than only the last one.
+/// Rendering stops with an error as soon as the stream is dropped, and a panic while rendering ends the stream with
+/// an error.
+///
+/// The template is moved to the render thread, hence `Send + 'static`: a template borrowing its data, like a view
+/// model with a lifetime, can't be streamed, render it with `render()` or `write_to_io()` instead.
+/// Templates that need `&mut self` are streamed with [`RenderStream::new`].
+pub struct RenderStream {
+    receiver: Receiver<Result<Bytes, fmt::Error>>,
+}
+
+impl RenderStream {
+    pub fn new<T: RsHtmlMut + Send + 'static>(mut template: T) -> Self {
+        let (sender, receiver) = channel(BUFFERED_CHUNKS);
+
+        RenderPool::get().execute(Box::new(move || {
+            let sender = RefCell::new(sender);
+            let buffer = RefCell::new(String::new());
+            let flush = || send_chunk(&sender, &buffer);
+
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                template.fmt_into(&mut SharedBuffer(&buffer), &flush).and_then(|_| flush())
+            }));
+            if let Err(err) = result.unwrap_or(Err(fmt::Error)) {
+                let _ = send(&mut sender.borrow_mut(), Err(err));
+            }
+        }));
+
+        RenderStream { receiver }
+    }
+}
+
+impl Stream for RenderStream {
+    type Item = Result<Bytes, fmt::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+struct SharedBuffer<'a>(&'a RefCell<String>);
+
+impl fmt::Write for SharedBuffer<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.borrow_mut().push_str(s);
+        Ok(())
+    }
+}
+
+fn send_chunk(sender: &RefCell<Sender<Result<Bytes, fmt::Error>>>, buffer: &RefCell<String>) -> fmt::Result {
+    let chunk = std::mem::take(&mut *buffer.borrow_mut());
+    if chunk.is_empty() {
+        return Ok(());
+    }
+
+    send(&mut sender.borrow_mut(), Ok(Bytes::from(chunk)))
+}
+
+/// Sends `item`, parking the render thread until the reader has room for it. Fails once the stream is dropped.
+fn send(sender: &mut Sender<Result<Bytes, fmt::Error>>, item: Result<Bytes, fmt::Error>) -> fmt::Result {
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        match sender.poll_ready(&mut cx) {
+            Poll::Ready(Ok(())) => return sender.start_send(item).map_err(|_| fmt::Error),
+            Poll::Ready(Err(_)) => return Err(fmt::Error),
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+/// Wakes the render thread parked in [`send`].
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The threads streams are rendered on. They are started as renders need them, up to [`MAX_RENDER_THREADS`],
+/// and kept for the next ones.
+struct RenderPool {
+    state: Mutex<PoolState>,
+    job_added: Condvar,
+}
+
+struct PoolState {
+    jobs: VecDeque<Job>,
+    threads: usize,
+    idle: usize,
+}
+
+impl RenderPool {
+    fn get() -> &'static RenderPool {
+        static POOL: OnceLock<RenderPool> = OnceLock::new();
+
+        POOL.get_or_init(|| RenderPool {
+            state: Mutex::new(PoolState {
+                jobs: VecDeque::new(),
+                threads: 0,
+                idle: 0,
+            }),
+            job_added: Condvar::new(),
+        })
+    }
+
+    /// Runs `job` on an idle thread, or on a new one if there is none and the pool isn't full.
+    fn execute(&'static self, job: Job) {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        state.jobs.push_back(job);
+
+        if state.jobs.len() > state.idle && state.threads < MAX_RENDER_THREADS {
+            state.threads += 1;
+            std::thread::spawn(|| self.work());
+        } else {
+            self.job_added.notify_one();
+        }
+    }
+
+    fn work(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+
+        loop {
+            if let Some(job) = state.jobs.pop_front() {
+                drop(state);
+                job();
+                state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+            } else {
+                state.idle += 1;
+                state = self.job_added.wait(state).unwrap_or_else(|err| err.into_inner());
+                state.idle -= 1;
+            }
+        }
+    }
+}
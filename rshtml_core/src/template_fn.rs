@@ -0,0 +1,58 @@
+use crate::escape::Context;
+use crate::traits::{Render, RsHtml};
+use crate::writer::DynWriter;
+use std::fmt;
+use std::fmt::Write;
+
+/// The value returned by `rshtml!` and by `#[template]` functions, a closure that writes the compiled template.
+///
+/// It is [`Render`], so it can be written into other templates as `@value`, and [`RsHtml`], so it can be rendered on its
+/// own with `render`, `write_to_io`, `render_bytes` or `render_stream`.
+///
+/// A closure can't be generic over the writer, so it writes through a `&mut dyn Write` whatever it is rendered into.
+pub struct TemplateFn<F>(F);
+
+impl<F> TemplateFn<F>
+where
+    F: Fn(&mut dyn Write, &dyn Fn() -> fmt::Result) -> fmt::Result,
+{
+    pub fn new(f: F) -> Self {
+        TemplateFn(f)
+    }
+
+    pub fn render(&self) -> Result<String, fmt::Error> {
+        RsHtml::render(self)
+    }
+}
+
+impl<F> RsHtml for TemplateFn<F>
+where
+    F: Fn(&mut dyn Write, &dyn Fn() -> fmt::Result) -> fmt::Result,
+{
+    fn fmt(&self, __f__: &mut dyn Write) -> fmt::Result {
+        (self.0)(__f__, &|| Ok(()))
+    }
+
+    fn render(&self) -> Result<String, fmt::Error> {
+        let mut buf = String::new();
+        (self.0)(&mut buf, &|| Ok(()))?;
+        Ok(buf)
+    }
+
+    fn fmt_with_flush(&self, __f__: &mut dyn Write, __flush__: &dyn Fn() -> fmt::Result) -> fmt::Result {
+        (self.0)(__f__, __flush__)
+    }
+}
+
+impl<F> Render for TemplateFn<F>
+where
+    F: Fn(&mut dyn Write, &dyn Fn() -> fmt::Result) -> fmt::Result,
+{
+    fn render_to<W: Write + ?Sized>(&self, f: &mut W, _: Context) -> fmt::Result {
+        (self.0)(&mut DynWriter(f), &|| Ok(()))
+    }
+
+    fn render_with_flush<W: Write + ?Sized>(&self, f: &mut W, _: Context, __flush__: &dyn Fn() -> fmt::Result) -> fmt::Result {
+        (self.0)(&mut DynWriter(f), __flush__)
+    }
+}
@@ -1,12 +1,19 @@
 mod ast_viewer;
-mod viewer;
 
+use crate::compiler::Compiler;
 use crate::config::Config;
-use crate::node::Node;
-use crate::parser::{RsHtmlParser, Rule};
-use crate::process_template;
-use pest::Parser;
-use std::fs;
+use crate::diagnostic::Diagnostics;
+use crate::escape::{Context, Escaper, escape};
+use crate::functions;
+use crate::markup::Markup;
+use crate::node::{ComponentParameter, ComponentParameterValue, ComponentProp, Node, Position, RustBlockContent, TextLineItem};
+use crate::optimizer::Optimizer;
+use crate::parser::RsHtmlParser;
+use crate::traits::{Render, RsHtml, RsHtmlMut};
+use crate::{TemplateSource, process_template};
+use std::fmt::Write;
+use std::io;
+use std::sync::Arc;
 use syn::__private::Span;
 
 #[test]
@@ -26,24 +33,10 @@ fn test_template_format() {
     assert!(matches!(ast, Node::Template(_)));
 }
 
-#[test]
-fn test_template_format_without_parsing() {
-    let template = fs::read_to_string("views/simple_expression.rs.html").unwrap();
-    let pairs = match RsHtmlParser::parse(Rule::template, template.as_str()) {
-        Ok(pairs) => pairs,
-        Err(err) => {
-            println!("{}", err);
-            return;
-        }
-    };
-
-    viewer::execute_pairs(pairs, 0, true);
-}
-
 #[test]
 pub fn test_process_simple() {
     let ident = syn::Ident::new("HomePage", Span::call_site());
-    process_template("continue_break.rs.html".to_string(), &ident);
+    process_template("continue_break.rs.html".to_string(), &ident, &syn::Generics::default(), false);
 }
 
 #[test]
@@ -52,3 +45,708 @@ pub fn test_config() {
     assert!(config.views.0.ends_with("views"));
     assert_eq!(config.views.1, "layout.rs.html".to_string());
 }
+
+#[test]
+pub fn test_escaper() {
+    let html = "<a href='/x'>Tom & \"Jerry\"</a> ok";
+    let mut buf = String::new();
+    write!(Escaper::new(&mut buf), "{}", html).unwrap();
+    assert_eq!(buf, "&lt;a href=&#39;&#x2F;x&#39;&gt;Tom &amp; &quot;Jerry&quot;&lt;&#x2F;a&gt; ok");
+
+    let mut buf = String::new();
+    write!(Escaper::new(&mut buf), "{}", 42).unwrap();
+    assert_eq!(buf, "42");
+}
+
+#[test]
+pub fn test_context_escapers() {
+    let escaped = |context: Context, value: &str| {
+        let mut buf = String::new();
+        escape(&mut buf, context, value).unwrap();
+        buf
+    };
+
+    assert_eq!(escaped(Context::Attribute, "a \"b\" `c`"), "a &quot;b&quot; &#96;c&#96;");
+    assert_eq!(escaped(Context::UnquotedAttribute, "a onclick=x"), "a&#32;onclick&#61;x");
+    assert_eq!(
+        escaped(Context::Url, "https://example.com/a b?x=1&y='2'"),
+        "https://example.com/a%20b?x=1&amp;y=%272%27"
+    );
+    assert_eq!(escaped(Context::Url, "JavaScript:alert(1)"), "about:invalid#rshtml-unsafe-url");
+    assert_eq!(escaped(Context::Url, "/users/1"), "/users/1");
+    assert_eq!(escaped(Context::UrlPart, "a&b/c d"), "a%26b%2Fc%20d");
+    assert_eq!(
+        escaped(Context::JsString, "it's </script>"),
+        "it\\u0027s\\u0020\\u003C\\u002Fscript\\u003E"
+    );
+    assert_eq!(escaped(Context::Script, "[\"</script>\"]"), "\"[\\\"\\u003C/script\\u003E\\\"]\"");
+    assert_eq!(escaped(Context::Script, "alert(1)"), "\"alert(1)\"");
+    assert_eq!(escaped(Context::Script, "42"), "\"42\"");
+    assert_eq!(escaped(Context::Script, "true"), "\"true\"");
+    assert_eq!(
+        escaped(Context::RawScript, "a</SCRIPT><!-- 1 < 2\u{2028}"),
+        "a<\\/SCRIPT><\\!-- 1 < 2\\u2028"
+    );
+
+    let mut json = String::new();
+    functions::json(&["</script>", "\u{2028}"]).render_to(&mut json, Context::Script).unwrap();
+    assert_eq!(json, "[\"\\u003C/script\\u003E\",\"\\u2028\"]");
+    assert_eq!(
+        escaped(Context::Css, "red; background: url(x)"),
+        "red\\3b  background\\3a  url\\28 x\\29 "
+    );
+}
+
+#[test]
+pub fn test_html_context() {
+    let template = Node::Template(vec![
+        Node::Text("<p>".to_string()),
+        Node::RustExprSimple("a".to_string(), true, Position::default()),
+        Node::Text("</p><a href=\"".to_string()),
+        Node::RustExprSimple("b".to_string(), true, Position::default()),
+        Node::Text("?q=".to_string()),
+        Node::RustExprSimple("c".to_string(), true, Position::default()),
+        Node::Text("\" title='".to_string()),
+        Node::RustExprSimple("d".to_string(), true, Position::default()),
+        Node::Text("' onclick=\"go('".to_string()),
+        Node::RustExprSimple("e".to_string(), true, Position::default()),
+        Node::Text("')\" style=\"color: ".to_string()),
+        Node::RustExprSimple("f".to_string(), true, Position::default()),
+        Node::Text("\">x</a><script>let a = ".to_string()),
+        Node::RustExprSimple("g".to_string(), true, Position::default()),
+        Node::Text("; let b = \"".to_string()),
+        Node::RustExprSimple("h".to_string(), true, Position::default()),
+        Node::Text("\";</script><style>".to_string()),
+        Node::RustExprSimple("i".to_string(), true, Position::default()),
+        Node::Text("</style><input value=".to_string()),
+        Node::RustExprSimple("j".to_string(), true, Position::default()),
+        Node::Text(">".to_string()),
+        Node::RustExprSimple("k".to_string(), true, Position::default()),
+    ]);
+
+    let ts = Compiler::new().compile(&template).unwrap().to_string();
+    let contexts: Vec<&str> = ts.split("Context :: ").skip(1).map(|s| s.split([' ', ')']).next().unwrap()).collect();
+
+    assert_eq!(
+        contexts,
+        [
+            "Html",
+            "Url",
+            "UrlPart",
+            "Attribute",
+            "JsString",
+            "Css",
+            "Script",
+            "JsString",
+            "Css",
+            "UnquotedAttribute",
+            "Html"
+        ]
+    );
+}
+
+#[test]
+pub fn test_optimizer() {
+    let template = Node::Template(vec![
+        Node::Text("<p>".to_string()),
+        Node::Comment(" comment ".to_string()),
+        Node::Raw("{raw}".to_string()),
+        Node::RustExprParen("(\"<b>\")".to_string(), false, Position::default()),
+        Node::RustExprSimple("a".to_string(), true, Position::default()),
+        Node::InnerText("</p>".to_string()),
+        Node::RustExprParen("(\"<b>\")".to_string(), true, Position::default()),
+        Node::Text("<a href=\"".to_string()),
+        Node::RustExprParen("(\"javascript:alert(1)\")".to_string(), true, Position::default()),
+        Node::Text("\">".to_string()),
+    ]);
+
+    let optimized = Optimizer::optimize(template);
+    let Node::Template(nodes) = &optimized else {
+        panic!("expected a template")
+    };
+    assert_eq!(nodes[0], Node::Text("<p>{raw}<b>".to_string()));
+    assert_eq!(nodes[2], Node::Text("</p>".to_string()));
+    assert_eq!(nodes.len(), 7);
+
+    let ts = Compiler::new().compile(&optimized).unwrap().to_string();
+    assert!(!ts.contains("write !"));
+    assert!(ts.contains("\"&lt;b&gt;\""));
+    assert!(ts.contains("\"about:invalid#rshtml-unsafe-url\""));
+}
+
+#[test]
+pub fn test_render() {
+    let mut buf = String::new();
+    "<b>".render_to(&mut buf, Context::Html).unwrap();
+    Markup("<b>").render_to(&mut buf, Context::Html).unwrap();
+    "<b>".render_to(&mut buf, Context::Raw).unwrap();
+    assert_eq!(buf, "&lt;b&gt;<b><b>");
+}
+
+struct HelloPage;
+
+impl RsHtml for HelloPage {
+    fn fmt(&self, __f__: &mut dyn Write) -> std::fmt::Result {
+        __f__.write_str("<p>hello</p>")
+    }
+
+    fn render(&self) -> Result<String, std::fmt::Error> {
+        let mut buf = String::new();
+        self.fmt(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+struct BrokenPipe;
+
+impl io::Write for BrokenPipe {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+pub fn test_write_to_io() {
+    let mut buf = Vec::new();
+    HelloPage.write_to_io(&mut buf).unwrap();
+    assert_eq!(buf, b"<p>hello</p>");
+    assert_eq!(HelloPage.render_bytes().unwrap(), b"<p>hello</p>");
+
+    let mut buf = String::new();
+    HelloPage.fmt_into(&mut buf, &|| Ok(())).unwrap();
+    assert_eq!(buf, "<p>hello</p>");
+
+    let err = HelloPage.write_to_io(BrokenPipe).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+}
+
+#[test]
+pub fn test_shared_render() {
+    let page: Box<dyn RsHtml + Send + Sync> = Box::new(HelloPage);
+    assert_eq!(page.render().unwrap(), "<p>hello</p>");
+
+    let page = Arc::new(HelloPage);
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let page = Arc::clone(&page);
+            std::thread::spawn(move || page.render().unwrap())
+        })
+        .collect();
+
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), "<p>hello</p>");
+    }
+
+    let mut page = HelloPage;
+    assert_eq!(RsHtmlMut::render(&mut page).unwrap(), "<p>hello</p>");
+}
+
+#[test]
+pub fn test_positions() {
+    let ident = syn::Ident::new("IncludePage", Span::call_site());
+    let source: syn::LitStr = syn::parse_quote! { "<p>\n  @(1 + 'ab')</p>" };
+    let ts = process_template(TemplateSource::Inline(source), &ident, &syn::Generics::default(), false).to_string();
+
+    assert!(ts.contains("Lex Error"));
+    assert!(ts.contains("--> inline template:2:4"));
+}
+
+#[test]
+pub fn test_invalid_rust() {
+    let ident = syn::Ident::new("InvalidPage", Span::call_site());
+    let compile = |source: &str| {
+        let source = syn::LitStr::new(source, Span::call_site());
+        process_template(TemplateSource::Inline(source), &ident, &syn::Generics::default(), false).to_string()
+    };
+
+    for (source, message, location) in [
+        (
+            "<p>\n@if self.a == { <b>a</b> }",
+            "Invalid Rust expression `if self.a ==`",
+            "--> inline template:2:2",
+        ),
+        ("@for x in { <b>a</b> }", "Invalid Rust expression `for x in`", "--> inline template:1:2"),
+        (
+            "@match self.a { Some(x) if => <b>a</b> }",
+            "Invalid Rust match arm `Some(x) if`",
+            "--> inline template:1:2",
+        ),
+        ("<p>@{ let = 1; }</p>", "Invalid Rust code block `let = 1;`", "--> inline template:1:5"),
+        ("<p>@(self.a +)</p>", "Invalid Rust expression `(self.a +)`", "--> inline template:1:5"),
+    ] {
+        let ts = compile(source);
+        assert!(ts.contains(message) && ts.contains(location), "{}", ts);
+    }
+}
+
+#[test]
+pub fn test_props() {
+    let source = "@props(items: HashMap<String, Vec<u8>>, map: fn(i32) -> i32 = |x| x + 1, count: usize = 1)";
+    let ast = RsHtmlParser::new().run_source(source, Config::default()).unwrap();
+
+    let prop = |name: &str, ty: &str, default: Option<&str>| ComponentProp {
+        name: name.to_string(),
+        ty: ty.to_string(),
+        default: default.map(str::to_string),
+    };
+    let props = vec![
+        prop("items", "HashMap<String, Vec<u8>>", None),
+        prop("map", "fn(i32) -> i32", Some("|x| x + 1")),
+        prop("count", "usize", Some("1")),
+    ];
+    assert!(
+        matches!(&ast, Node::Template(nodes) if nodes[0] == Node::PropsDirective(props, Position { file: "inline template".to_string(), line: 1, column: 2 }))
+    );
+
+    let source: syn::LitStr = syn::parse_quote! { "@use \"Alert.rs.html\"\n<Alert titel=\"x\"/>\n<Alert title=\"x\" count=\"many\"/>\n@Alert(title: true) {}\n<Alert title=\"a\" title=\"b\"/>" };
+    let ident = syn::Ident::new("PropsPage", Span::call_site());
+    let ts = process_template(TemplateSource::Inline(source), &ident, &syn::Generics::default(), false).to_string();
+
+    for message in [
+        "unknown prop `titel` for component Alert, its props are `title`, `count`, `ratio`, `footer`",
+        "missing prop `title: &str` for component Alert",
+        "the prop `count` of component Alert is a `i32`, got a string",
+        "the prop `title` of component Alert is a `&str`, got a bool",
+        "(declared at views/Alert.rs.html:1:2)",
+        "--> inline template:2:1",
+        "--> inline template:3:1",
+        "--> inline template:4:2",
+        "the parameter `title` of component Alert is given twice",
+        "--> inline template:5:1",
+    ] {
+        assert!(ts.contains(message), "{}: {}", message, ts);
+    }
+
+    // rustc's error for a value of the wrong type names the prop and the call
+    let source: syn::LitStr = syn::parse_quote! { "@use \"Alert.rs.html\"\n<p><Alert title=@self.title count=@self.count/></p>" };
+    let ts = process_template(TemplateSource::Inline(source), &ident, &syn::Generics::default(), false).to_string();
+
+    assert!(ts.contains("struct __prop_title_Alert_inline_template_2_4 < T > (T)"), "{}", ts);
+    assert!(
+        ts.contains("let __prop_count_Alert_inline_template_2_4 (value) : __prop_count_Alert_inline_template_2_4 < i32 >"),
+        "{}",
+        ts
+    );
+}
+
+#[test]
+pub fn test_slots() {
+    let source: syn::LitStr = syn::parse_quote! { "@use \"Panel.rs.html\"\n<Panel><slot:heading>a</slot:heading></Panel>\n<Panel><slot:footer>a</slot:footer><slot:footer>b</slot:footer></Panel>\n<slot:header>c</slot:header> @slot(\"header\")" };
+    let ident = syn::Ident::new("SlotsPage", Span::call_site());
+    let ts = process_template(TemplateSource::Inline(source), &ident, &syn::Generics::default(), false).to_string();
+
+    for message in [
+        "component Panel has no slot `heading`, its slots are `header`, `footer`",
+        "--> inline template:2:8",
+        "the slot `footer` of component Panel is given twice",
+        "--> inline template:3:36",
+        "<slot:header> must be directly in the body of a component",
+        "--> inline template:4:1",
+        "@slot(\\\"header\\\") can only be used in a component",
+        "--> inline template:4:31",
+    ] {
+        assert!(ts.contains(message), "{}: {}", message, ts);
+    }
+
+    // content given by a call is escaped for the one place the component writes it, and ends where it starts
+    let source: syn::LitStr =
+        syn::parse_quote! { "@use \"Panel.rs.html\"\n@use \"Tip.rs.html\" as Tip\n<Panel>a <b title=\"b</Panel>\n<Tip label=\"c\">d</Tip>" };
+    let ts = process_template(TemplateSource::Inline(source), &ident, &syn::Generics::default(), false).to_string();
+
+    for message in [
+        "the child content of component Panel ends in another place of the html than it starts, close the tags, comments and quotes it opens",
+        "--> inline template:3:1",
+        "@child_content is written in two different places of the html, like in text and in an attribute, write it in one",
+        "--> inline template:4:1",
+    ] {
+        assert!(ts.contains(message), "{}: {}", message, ts);
+    }
+}
+
+#[test]
+pub fn test_component_scope() {
+    let source: syn::LitStr = syn::parse_quote! { "@use \"Badge.rs.html\"\n<p><Badge label=\"new\"/></p>" };
+    let ident = syn::Ident::new("BadgePage", Span::call_site());
+    let ts = process_template(TemplateSource::Inline(source), &ident, &syn::Generics::default(), false).to_string();
+
+    assert!(
+        ts.contains("component Badge can't use `self`, pass what it needs as a parameter"),
+        "{}",
+        ts
+    );
+    assert!(ts.contains("--> views/Badge.rs.html:2:4"), "{}", ts);
+
+    let source: syn::LitStr = syn::parse_quote! { "@use \"Title.rs.html\"\n<Title/>" };
+    let ts = process_template(TemplateSource::Inline(source), &ident, &syn::Generics::default(), false).to_string();
+
+    assert!(
+        ts.contains("component Title can't use `self`, pass what it needs as a parameter"),
+        "{}",
+        ts
+    );
+    assert!(ts.contains("--> views/Title.rs.html:1:2"), "{}", ts);
+}
+
+#[test]
+pub fn test_shared_components() {
+    let source: syn::LitStr =
+        syn::parse_quote! { "@use \"Alert.rs.html\"\n<Alert title=\"a\"/>\n<Alert title=\"b\" count=2/>\n@Alert(title: \"c\") {body}" };
+    let ident = syn::Ident::new("AlertsPage", Span::call_site());
+    let ts = process_template(TemplateSource::Inline(source), &ident, &syn::Generics::default(), false).to_string();
+
+    assert_eq!(ts.matches("fn __component_Alert_0__").count(), 1, "{}", ts);
+    assert_eq!(ts.matches("__component_Alert_0__ (__f__").count(), 3, "{}", ts);
+
+    let source: syn::LitStr = syn::parse_quote! { "@use inline \"Alert.rs.html\"\n<Alert title=\"a\"/>\n<Alert title=\"b\" count=2/>" };
+    let ts = process_template(TemplateSource::Inline(source), &ident, &syn::Generics::default(), false).to_string();
+
+    assert_eq!(ts.matches("fn __component__").count(), 2, "{}", ts);
+    assert!(!ts.contains("__component_Alert_0__"), "{}", ts);
+}
+
+#[test]
+pub fn test_diagnostics() {
+    let Err(Diagnostics(diagnostics)) = RsHtmlParser::new().run("diagnostics.rs.html", Config::default()) else {
+        panic!("expected the included templates to fail");
+    };
+
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].position.file, "views/diagnostics_part.rs.html");
+    assert_eq!(diagnostics[1].position.file, "views/diagnostics_component.rs.html");
+    assert_eq!(
+        diagnostics[0].included_from,
+        [Position {
+            file: "views/diagnostics.rs.html".to_string(),
+            line: 2,
+            column: 9,
+        }]
+    );
+
+    let message = Diagnostics(diagnostics).to_string();
+    assert!(message.contains(" = note: included from views/diagnostics.rs.html:3:6"));
+    assert!(message.contains("1 | <p>@(unclosed</p>\n  |     ^"));
+    assert!(message.contains("2 template errors"));
+
+    let source: syn::LitStr = syn::parse_quote! { "<p>@(self.a +)</p>\n<p>@match self.b { Some(x) if => <i>b</i> }</p>" };
+    let ident = syn::Ident::new("BrokenPage", Span::call_site());
+    let ts = process_template(TemplateSource::Inline(source), &ident, &syn::Generics::default(), false).to_string();
+
+    assert!(ts.contains("--> inline template:1:5"));
+    assert!(ts.contains("--> inline template:2:5"));
+    assert!(ts.contains("2 template errors"));
+}
+
+#[test]
+pub fn test_recovery() {
+    let (ast, Diagnostics(diagnostics)) = RsHtmlParser::new().run_partial("recovery.rs.html", Config::default());
+
+    let lines: Vec<usize> = diagnostics.iter().map(|diagnostic| diagnostic.position.line).collect();
+    assert_eq!(lines, [2, 5, 8]);
+
+    let Node::Template(nodes) = &ast else { panic!("expected a template") };
+    assert!(matches!(&nodes[1], Node::RustExprSimple(expr, ..) if expr == "self.title"));
+    assert!(matches!(&nodes[6], Node::RustExpr(clauses, _) if clauses[0].0 == "for x in self.items"));
+    assert!(matches!(&nodes[9], Node::RustExprSimple(expr, ..) if expr == "self.footer"));
+
+    let source = "@match self.c { => <b>c</b> }\n".repeat(100);
+    let Err(Diagnostics(diagnostics)) = RsHtmlParser::new().run_source(&source, Config::default()) else {
+        panic!("expected errors")
+    };
+    assert_eq!(diagnostics.len(), 65);
+    assert_eq!(diagnostics[64].message, "too many errors, the rest of inline template isn't checked");
+    assert_eq!(diagnostics[64].position.line, 65);
+
+    // brackets left open don't make the scan of a parenthesized expression go over the rest again for each of them
+    let source = format!("<p>@({}</p>", "(".repeat(64));
+    assert!(RsHtmlParser::new().run_source(&source, Config::default()).is_err());
+}
+
+/// Compares the tree parsed from each template in `views` with its snapshot in `src/tests/snapshots`.
+/// Run with `UPDATE_SNAPSHOTS=1` to write the snapshots again after a change to the parser.
+#[test]
+pub fn test_parser_snapshots() {
+    let snapshots = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/snapshots");
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+
+    let mut views: Vec<_> = std::fs::read_dir("views")
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    views.sort();
+
+    for view in views {
+        let (ast, Diagnostics(diagnostics)) = RsHtmlParser::new().run_partial(&view, Config::default());
+
+        let mut snapshot = format!("{:#?}\n", ast);
+        for diagnostic in diagnostics {
+            let position = &diagnostic.position;
+            writeln!(
+                snapshot,
+                "error: {} --> {}:{}:{}",
+                diagnostic.message, position.file, position.line, position.column
+            )
+            .unwrap();
+        }
+
+        let path = snapshots.join(view.replace(".rs.html", ".snap"));
+        if update {
+            std::fs::write(&path, snapshot).unwrap();
+            continue;
+        }
+
+        let expected = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            snapshot == expected,
+            "the tree of {} doesn't match {}:\n{}",
+            view,
+            path.display(),
+            snapshot
+        );
+    }
+}
+
+/// Compares the tree parsed from each template in `views` that has a snapshot in `src/tests/pest` with that snapshot,
+/// which the pest parser made before it was replaced.
+#[test]
+pub fn test_pest_snapshots() {
+    let snapshots = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/pest");
+
+    let mut names: Vec<_> = std::fs::read_dir(&snapshots)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+
+    for name in names {
+        let view = name.replace(".snap", ".rs.html");
+        let ast = RsHtmlParser::new().run(&view, Config::default()).unwrap();
+
+        let tree = without_use_inline_flags(&format!("{:#?}\n", ast));
+        let expected = std::fs::read_to_string(snapshots.join(&name)).unwrap();
+        assert!(
+            tree == expected,
+            "the tree of {} doesn't match the one the pest parser made:\n{}",
+            view,
+            tree
+        );
+    }
+}
+
+/// Drops the `inline` flag `@use` directives have had since the pest parser, from a tree printed with `{:#?}`.
+fn without_use_inline_flags(tree: &str) -> String {
+    let lines: Vec<_> = tree.lines().collect();
+    let mut uses = Vec::new();
+    let mut kept = String::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let indent = line.len() - line.trim_start().len();
+        if line.trim_start() == "UseDirective(" {
+            uses.push(indent);
+        }
+        let use_indent = uses.last().map(|indent| " ".repeat(*indent));
+        if let Some(use_indent) = use_indent
+            && *line == format!("{}    false,", use_indent)
+            && lines.get(i + 1) == Some(&format!("{}),", use_indent).as_str())
+        {
+            uses.pop();
+            continue;
+        }
+        kept.push_str(line);
+        kept.push('\n');
+    }
+
+    kept
+}
+
+#[test]
+pub fn test_inline_source_error() {
+    let source: syn::LitStr = syn::parse_quote! { "<p>\n@if true { <b> \n" };
+    let ident = syn::Ident::new("InlinePage", Span::call_site());
+    let ts = process_template(TemplateSource::Inline(source), &ident, &syn::Generics::default(), false).to_string();
+
+    assert!(ts.contains("compile_error"));
+    assert!(ts.contains("inline template"));
+    assert!(ts.contains("--> inline template:3:1"));
+}
+
+#[test]
+pub fn test_parser() {
+    let source = r#"<h1>@self.title</h1>
+@* note *@
+@if self.ok { <b>@@ok</b> } else { no }
+@match self.n { 1 => { one } _ => (self.n + 1) }
+@{ let x = "}";
+   @: x is @x
+}
+<Card title="t" big>@#self.body</Card>"#;
+
+    let ast = RsHtmlParser::new().run_source(source, Config::default()).unwrap();
+
+    let position = |line, column| Position {
+        file: "inline template".to_string(),
+        line,
+        column,
+    };
+    let expected = Node::Template(vec![
+        Node::Text("<h1>".to_string()),
+        Node::RustExprSimple("self.title".to_string(), true, position(1, 6)),
+        Node::Text("</h1>\n".to_string()),
+        Node::Comment(" note ".to_string()),
+        Node::Text("\n".to_string()),
+        Node::RustExpr(
+            vec![
+                ("if self.ok".to_string(), vec![Node::InnerText("<b>@ok</b> ".to_string())]),
+                ("else".to_string(), vec![Node::InnerText("no ".to_string())]),
+            ],
+            position(3, 2),
+        ),
+        Node::Text("\n".to_string()),
+        Node::MatchExpr(
+            "match self.n ".to_string(),
+            vec![
+                ("1 ".to_string(), vec![Node::InnerText("one ".to_string())]),
+                (
+                    "_ ".to_string(),
+                    vec![Node::RustExprParen("(self.n + 1)".to_string(), true, position(4, 35))],
+                ),
+            ],
+            position(4, 2),
+        ),
+        Node::Text("\n".to_string()),
+        Node::RustBlock(
+            vec![
+                RustBlockContent::Code("let x = \"}\";\n   ".to_string()),
+                RustBlockContent::TextLine(vec![
+                    TextLineItem::Text(" x is ".to_string()),
+                    TextLineItem::RustExprSimple("x".to_string(), true),
+                ]),
+            ],
+            position(5, 2),
+        ),
+        Node::Text("\n".to_string()),
+        Node::Component(
+            "Card".to_string(),
+            vec![
+                ComponentParameter {
+                    name: "title".to_string(),
+                    value: ComponentParameterValue::String("t".to_string()),
+                },
+                ComponentParameter {
+                    name: "big".to_string(),
+                    value: ComponentParameterValue::Bool(true),
+                },
+            ],
+            vec![Node::RustExprSimple("self.body".to_string(), false, position(8, 22))],
+            position(8, 1),
+        ),
+    ]);
+
+    assert_eq!(ast, expected);
+}
+
+#[test]
+pub fn test_parser_errors() {
+    for (source, message, line, column) in [
+        (
+            "<p>@include(part)</p>",
+            "expected the path of the included file in quotes in @include",
+            1,
+            13,
+        ),
+        (
+            "@section(\"title\" self.title)",
+            "expected `,` and the content of the section in @section",
+            1,
+            18,
+        ),
+        ("@section main <p>", "expected `{` to open @section main", 1, 15),
+        ("@if self.a { <b>", "expected `}` to close the @if self.a block", 1, 17),
+        (
+            "@for x in self.items <li>",
+            "expected `{` to open the @for x in self.items <li> block",
+            1,
+            26,
+        ),
+        ("@match self.a { Some(x) => @x }", "expected a value after `Some(x) =>` in @match", 1, 28),
+        ("@card(title: ) { }", "expected a value for the parameter `title` of @card", 1, 14),
+        ("<Card>\n<p>", "expected `</Card>` to close the <Card> tag opened at 1:1", 2, 4),
+        ("<p>@raw { x</p>", "unclosed @raw block", 1, 9),
+        ("<p>@{ let x = 1;</p>", "unclosed code block", 1, 5),
+        ("@* note", "unclosed comment", 1, 1),
+        (
+            "<Card><slot:header>a</Card>",
+            "expected `</slot:header>` to close the <slot:header> tag opened at 1:7",
+            1,
+            21,
+        ),
+        ("@props(title &str)", "expected `:` and the type of the prop `title` in @props", 1, 14),
+        (
+            "<p>@slot(\"main-header\")</p>",
+            "the name of a slot is an identifier like `main_header`, got \"main-header\" in @slot",
+            1,
+            5,
+        ),
+        ("@if name == \"a { <b>", "unterminated string literal", 1, 13),
+        ("@{ let s = r#\"}\"; }", "unterminated raw string literal", 1, 12),
+        ("@{ /* } */ /* }", "unclosed block comment", 1, 12),
+        ("<p>@(self.a(\"b)</p>", "unterminated string literal", 1, 13),
+    ] {
+        let Err(Diagnostics(diagnostics)) = RsHtmlParser::new().run_source(source, Config::default()) else {
+            panic!("expected `{}` to fail", source);
+        };
+
+        assert!(diagnostics[0].message.starts_with(message), "{}: {}", source, diagnostics[0].message);
+        assert_eq!(
+            (diagnostics[0].position.line, diagnostics[0].position.column),
+            (line, column),
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+pub fn test_at_signs() {
+    let source = r#"<p>Mail support@example.com or @@rshtml, @name</p>
+<p>total@(price) @match kind { Web => { none } Mail => <i>team@example.com</i> }</p>
+<style>
+@media (max-width: 600px) { .a { color: @color; } }
+@font-face { font-family: x; }
+</style>
+@media"#;
+
+    let Node::Template(nodes) = RsHtmlParser::new().run_source(source, Config::default()).unwrap() else {
+        panic!("expected a template");
+    };
+
+    let position = |line, column| Position {
+        file: "inline template".to_string(),
+        line,
+        column,
+    };
+    let expected = vec![
+        Node::Text("<p>Mail support@example.com or @rshtml, ".to_string()),
+        Node::RustExprSimple("name".to_string(), true, position(1, 43)),
+        Node::Text("</p>\n<p>total".to_string()),
+        Node::RustExprParen("(price)".to_string(), true, position(2, 10)),
+        Node::Text(" ".to_string()),
+        Node::MatchExpr(
+            "match kind ".to_string(),
+            vec![
+                ("Web ".to_string(), vec![Node::InnerText("none ".to_string())]),
+                ("Mail ".to_string(), vec![Node::InnerText("<i>team@example.com</i> ".to_string())]),
+            ],
+            position(2, 19),
+        ),
+        Node::Text("</p>\n<style>\n@media (max-width: 600px) { .a { color: ".to_string()),
+        Node::RustExprSimple("color".to_string(), true, position(4, 42)),
+        Node::Text("; } }\n@font-face { font-family: x; }\n</style>\n".to_string()),
+        Node::RustExprSimple("media".to_string(), true, position(7, 2)),
+    ];
+
+    assert_eq!(nodes, expected);
+}
@@ -74,19 +74,19 @@ pub fn view_node(node: &Node, indent: usize) {
         Node::RenderDirective(path) => {
             println!("- RenderDirective: {:?}", path);
         }
-        Node::RustBlock(contents) => {
+        Node::RustBlock(contents, _) => {
             println!("- RustBlock:");
             for content in contents {
                 view_rust_block_content(content, indent + 1);
             }
         }
-        Node::RustExprSimple(expr, _) => {
+        Node::RustExprSimple(expr, ..) => {
             println!("- RustExprSimple: {:?}", expr);
         }
-        Node::RustExprParen(expr, _) => {
+        Node::RustExprParen(expr, ..) => {
             println!("- RustExprParen: {:?}", expr);
         }
-        Node::RustExpr(clauses) => {
+        Node::RustExpr(clauses, _) => {
             println!("- RustExpr:");
             for (condition, nodes) in clauses {
                 print_indent(indent + 1);
@@ -96,7 +96,7 @@ pub fn view_node(node: &Node, indent: usize) {
                 }
             }
         }
-        Node::MatchExpr(head, arms) => {
+        Node::MatchExpr(head, arms, _) => {
             println!("- MatchExpr:");
             print_indent(indent + 1);
             println!("- Clause: {:?}", head);
@@ -117,7 +117,7 @@ pub fn view_node(node: &Node, indent: usize) {
             print_indent(indent + 1);
             match body {
                 SectionDirectiveContent::Text(s) => println!("- StringLine: {:?}", s),
-                SectionDirectiveContent::RustExprSimple(s, _) => println!("- RustExprSimple: {:?}", s),
+                SectionDirectiveContent::RustExprSimple(s, ..) => println!("- RustExprSimple: {:?}", s),
             }
         }
         Node::SectionBlock(section_head, body) => {
@@ -131,7 +131,7 @@ pub fn view_node(node: &Node, indent: usize) {
         Node::RenderBody => {
             println!("- RenderBody");
         }
-        Node::Component(name, parameters, body) => {
+        Node::Component(name, parameters, body, _) => {
             println!("- Component:");
             print_indent(indent + 1);
             println!("- Name: {:?}", name);
@@ -166,14 +166,30 @@ pub fn view_node(node: &Node, indent: usize) {
         Node::ChildContent => {
             println!("- ChildContent");
         }
+        Node::Slot(name, nodes, _) => {
+            println!("- Slot: {:?}", name);
+            for node in nodes {
+                view_node(node, indent + 1);
+            }
+        }
+        Node::SlotDirective(name, _) => println!("- SlotDirective: {:?}", name),
+        Node::PropsDirective(props, _) => {
+            println!("- PropsDirective:");
+            for prop in props {
+                print_indent(indent + 1);
+                println!("- {}: {} = {:?}", prop.name, prop.ty, prop.default);
+            }
+        }
         Node::Raw(s) => println!("- Raw: {:?}", s),
-        Node::UseDirective(component_name, import_path, component) => {
+        Node::UseDirective(component_name, import_path, component, inline) => {
             println!("- UseDirective:");
             print_indent(indent + 1);
             println!("- ComponentName: {:?}", component_name);
             print_indent(indent + 1);
             println!("- ImportPath: {:#?}", import_path);
             print_indent(indent + 1);
+            println!("- Inline: {}", inline);
+            print_indent(indent + 1);
             println!("- Component:");
             view_node(component, indent + 2);
         }
@@ -183,5 +199,8 @@ pub fn view_node(node: &Node, indent: usize) {
         Node::BreakDirective => {
             println!("- BreakDirective");
         }
+        Node::FlushDirective => {
+            println!("- FlushDirective");
+        }
     }
 }
@@ -1,8 +1,214 @@
-use std::fmt::Write;
+use crate::escape::{Context, escape};
+#[cfg(feature = "stream")]
+use crate::stream::RenderStream;
+use crate::writer::{DynWriter, IoWriter};
+use std::fmt::{Display, Write};
+use std::io;
+use std::sync::Arc;
 
+/// A template whose rendering only reads its data, so it can be rendered through `&self`,
+/// from an `Arc` or from several threads at once. This is what `#[derive(RsHtml)]` implements.
+///
+/// [`RsHtml::fmt_into`] is generic over the writer, the generated code for it is monomorphized
+/// for every writer type. [`RsHtml::fmt`] keeps the trait object-safe, going through `dyn fmt::Write`.
 pub trait RsHtml {
+    fn fmt(&self, __f__: &mut dyn Write) -> std::fmt::Result;
+    fn render(&self) -> Result<String, std::fmt::Error>;
+
+    /// Like [`RsHtml::fmt`], calling `__flush__` at every `@flush` directive of the template.
+    fn fmt_with_flush(&self, __f__: &mut dyn Write, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result {
+        self.fmt(__f__)
+    }
+
+    /// Like [`RsHtml::fmt_with_flush`], writing to a concrete writer type instead of a trait object.
+    fn fmt_into<W: Write + ?Sized>(&self, __f__: &mut W, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result
+    where
+        Self: Sized,
+    {
+        self.fmt_with_flush(&mut DynWriter(__f__), __flush__)
+    }
+
+    /// Streams the rendered template into `writer` (a socket, file, `BufWriter`, ...) without building a `String` first.
+    fn write_to_io(&self, writer: impl io::Write) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        let mut writer = IoWriter::new(writer);
+        let result = self.fmt_into(&mut writer, &|| Ok(()));
+        writer.finish(result).map(|_| ())
+    }
+
+    fn render_bytes(&self) -> Result<Vec<u8>, std::fmt::Error>
+    where
+        Self: Sized,
+    {
+        self.render().map(String::into_bytes)
+    }
+
+    /// Renders the template on a separate thread, yielding the output as a chunk at every `@flush` and at the end.
+    /// The template is moved there, so one that borrows its data can't be streamed.
+    #[cfg(feature = "stream")]
+    fn render_stream(self) -> RenderStream
+    where
+        Self: Sized + Send + 'static,
+    {
+        RenderStream::new(self)
+    }
+}
+
+impl<T: RsHtml + ?Sized> RsHtml for &T {
+    fn fmt(&self, __f__: &mut dyn Write) -> std::fmt::Result {
+        (**self).fmt(__f__)
+    }
+
+    fn render(&self) -> Result<String, std::fmt::Error> {
+        (**self).render()
+    }
+
+    fn fmt_with_flush(&self, __f__: &mut dyn Write, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result {
+        (**self).fmt_with_flush(__f__, __flush__)
+    }
+}
+
+impl<T: RsHtml + ?Sized> RsHtml for Box<T> {
+    fn fmt(&self, __f__: &mut dyn Write) -> std::fmt::Result {
+        (**self).fmt(__f__)
+    }
+
+    fn render(&self) -> Result<String, std::fmt::Error> {
+        (**self).render()
+    }
+
+    fn fmt_with_flush(&self, __f__: &mut dyn Write, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result {
+        (**self).fmt_with_flush(__f__, __flush__)
+    }
+}
+
+impl<T: RsHtml + ?Sized> RsHtml for Arc<T> {
+    fn fmt(&self, __f__: &mut dyn Write) -> std::fmt::Result {
+        (**self).fmt(__f__)
+    }
+
+    fn render(&self) -> Result<String, std::fmt::Error> {
+        (**self).render()
+    }
+
+    fn fmt_with_flush(&self, __f__: &mut dyn Write, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result {
+        (**self).fmt_with_flush(__f__, __flush__)
+    }
+}
+
+/// A template that changes its own data while rendering, implemented by `#[derive(RsHtml)]` with `#[rshtml(mut)]`.
+///
+/// Every [`RsHtml`] template is also an `RsHtmlMut`, so code that accepts this trait accepts both kinds.
+pub trait RsHtmlMut {
     fn fmt(&mut self, __f__: &mut dyn Write) -> std::fmt::Result;
     fn render(&mut self) -> Result<String, std::fmt::Error>;
+
+    /// Like [`RsHtmlMut::fmt`], calling `__flush__` at every `@flush` directive of the template.
+    fn fmt_with_flush(&mut self, __f__: &mut dyn Write, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result {
+        self.fmt(__f__)
+    }
+
+    /// Like [`RsHtmlMut::fmt_with_flush`], writing to a concrete writer type instead of a trait object.
+    fn fmt_into<W: Write + ?Sized>(&mut self, __f__: &mut W, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result
+    where
+        Self: Sized,
+    {
+        self.fmt_with_flush(&mut DynWriter(__f__), __flush__)
+    }
+
+    fn write_to_io(&mut self, writer: impl io::Write) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        let mut writer = IoWriter::new(writer);
+        let result = self.fmt_into(&mut writer, &|| Ok(()));
+        writer.finish(result).map(|_| ())
+    }
+
+    fn render_bytes(&mut self) -> Result<Vec<u8>, std::fmt::Error>
+    where
+        Self: Sized,
+    {
+        self.render().map(String::into_bytes)
+    }
+}
+
+impl<T: RsHtml + ?Sized> RsHtmlMut for T {
+    fn fmt(&mut self, __f__: &mut dyn Write) -> std::fmt::Result {
+        <T as RsHtml>::fmt(self, __f__)
+    }
+
+    fn render(&mut self) -> Result<String, std::fmt::Error> {
+        <T as RsHtml>::render(self)
+    }
+
+    fn fmt_with_flush(&mut self, __f__: &mut dyn Write, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result {
+        <T as RsHtml>::fmt_with_flush(self, __f__, __flush__)
+    }
+
+    fn fmt_into<W: Write + ?Sized>(&mut self, __f__: &mut W, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result
+    where
+        Self: Sized,
+    {
+        <T as RsHtml>::fmt_into(self, __f__, __flush__)
+    }
+}
+
+/// A value that can be written into a template.
+///
+/// The compiler calls this for every expression. Plain `Display` values are escaped for the context
+/// they are written into, types that already hold HTML, like [`Markup`](crate::markup::Markup), write themselves as is.
+pub trait Render {
+    fn render_to<W: Write + ?Sized>(&self, f: &mut W, context: Context) -> std::fmt::Result;
+
+    /// Like [`Render::render_to`], calling `__flush__` at every `@flush` directive of a value that is a template.
+    fn render_with_flush<W: Write + ?Sized>(&self, f: &mut W, context: Context, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result {
+        self.render_to(f, context)
+    }
+}
+
+impl<T: Display + ?Sized> Render for T {
+    fn render_to<W: Write + ?Sized>(&self, f: &mut W, context: Context) -> std::fmt::Result {
+        escape(f, context, self)
+    }
+}
+
+/// An expression of a template, on its way to [`RenderValue`] or [`RenderRef`].
+///
+/// The compiler writes every expression as `(&&Dispatch(&value)).__rshtml_render_to(..)`. Method resolution
+/// tries the impl on `&Dispatch` before the one on `Dispatch`, so a value that is [`Render`] is rendered as such
+/// even when it is also a template, and no method of the value's own type can take the call over.
+#[doc(hidden)]
+pub struct Dispatch<'a, T: ?Sized>(pub &'a T);
+
+/// Renders the expressions whose value is [`Render`].
+#[doc(hidden)]
+pub trait RenderValue {
+    fn __rshtml_render_to<W: Write + ?Sized>(&self, f: &mut W, context: Context, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result;
+}
+
+impl<T: Render + ?Sized> RenderValue for &Dispatch<'_, T> {
+    fn __rshtml_render_to<W: Write + ?Sized>(&self, f: &mut W, context: Context, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result {
+        Render::render_with_flush(self.0, f, context, __flush__)
+    }
+}
+
+/// Renders a nested template straight into the parent's writer, so `@self.sidebar` needs neither
+/// an intermediate `String` nor the `#` prefix.
+///
+/// A template that needs `&mut self` can't be rendered through the shared reference an expression gives,
+/// write it with `@#(self.child.render()?)`.
+#[doc(hidden)]
+pub trait RenderRef {
+    fn __rshtml_render_to<W: Write + ?Sized>(&self, f: &mut W, context: Context, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result;
+}
+
+impl<T: RsHtml> RenderRef for Dispatch<'_, T> {
+    fn __rshtml_render_to<W: Write + ?Sized>(&self, f: &mut W, _: Context, __flush__: &dyn Fn() -> std::fmt::Result) -> std::fmt::Result {
+        RsHtml::fmt_into(self.0, f, __flush__)
+    }
 }
 
 pub(crate) trait IsEscaped {
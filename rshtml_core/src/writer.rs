@@ -0,0 +1,49 @@
+use std::fmt;
+use std::io;
+
+/// Adapts an `io::Write` to `fmt::Write`, keeping the I/O error that `fmt::Error` can't carry.
+pub struct IoWriter<W: io::Write> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoWriter<W> {
+    pub fn new(inner: W) -> Self {
+        IoWriter { inner, error: None }
+    }
+
+    /// Turns the result of a render into an `io::Result`, returning the original I/O error if there was one.
+    pub fn finish(self, result: fmt::Result) -> io::Result<W> {
+        match (result, self.error) {
+            (_, Some(err)) => Err(err),
+            (Err(_), None) => Err(io::Error::other("template formatting failed")),
+            (Ok(()), None) => Ok(self.inner),
+        }
+    }
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Passes a writer of any type, sized or not, where a `&mut dyn fmt::Write` is expected.
+pub struct DynWriter<'a, W: fmt::Write + ?Sized>(pub &'a mut W);
+
+impl<W: fmt::Write + ?Sized> fmt::Write for DynWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_str(s)
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.0.write_char(c)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> fmt::Result {
+        self.0.write_fmt(args)
+    }
+}
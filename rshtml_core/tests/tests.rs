@@ -1,6 +1,6 @@
 use proc_macro2::TokenStream;
 use quote::quote;
-use rshtml_core::process_template;
+use rshtml_core::{TemplateSource, process_enum_template, process_function_template, process_inline_template, process_template};
 use std::fs::File;
 use std::io::Write;
 use std::str::FromStr;
@@ -14,18 +14,43 @@ fn prepare(
     values: TokenStream,
     functions: TokenStream,
 ) -> std::io::Result<()> {
-    let struct_name_ts = TokenStream::from_str(struct_name).unwrap();
-    let ident = syn::Ident::new(struct_name, Span::call_site());
-    let ts = process_template(template_path.to_string(), &ident);
+    prepare_with_items(struct_name, template_path, fields, values, functions, quote! {})
+}
 
-    let test_code_str = quote! {
-        pub use rshtml_core::traits::*;
+fn prepare_mut(struct_name: &str, template_path: &str, fields: TokenStream, values: TokenStream, functions: TokenStream) -> std::io::Result<()> {
+    prepare_template(struct_name, template_path, true, fields, values, functions, quote! {})
+}
 
-        mod rshtml {
-            pub use rshtml_core::functions;
-            pub use rshtml_core::traits;
-        }
+fn prepare_with_items(
+    struct_name: &str,
+    template_path: &str,
+    fields: TokenStream,
+    values: TokenStream,
+    functions: TokenStream,
+    items: TokenStream,
+) -> std::io::Result<()> {
+    prepare_template(struct_name, template_path, false, fields, values, functions, items)
+}
+
+fn prepare_template(
+    struct_name: &str,
+    template_path: &str,
+    mutable: bool,
+    fields: TokenStream,
+    values: TokenStream,
+    functions: TokenStream,
+    items: TokenStream,
+) -> std::io::Result<()> {
+    let struct_name_ts = TokenStream::from_str(struct_name).unwrap();
+    let ident = syn::Ident::new(struct_name, Span::call_site());
+    let ts = process_template(template_path.to_string(), &ident, &syn::Generics::default(), mutable);
+    let page_ts = if mutable {
+        quote! {mut page}
+    } else {
+        quote! {page}
+    };
 
+    pass(quote! {
         struct #struct_name_ts {
             #fields
         }
@@ -36,13 +61,31 @@ fn prepare(
 
         #ts
 
+        #items
+
         fn main() {
-            let mut page = #struct_name_ts {
+            let #page_ts = #struct_name_ts {
                 #values
             };
 
             println!("{}", page.render().unwrap());
         }
+    })
+}
+
+fn pass(code: TokenStream) -> std::io::Result<()> {
+    let test_code_str = quote! {
+        pub use rshtml_core::traits::*;
+
+        mod rshtml {
+            pub use rshtml_core::escape;
+            pub use rshtml_core::functions;
+            pub use rshtml_core::markup::Markup;
+            pub use rshtml_core::template_fn::TemplateFn;
+            pub use rshtml_core::traits;
+        }
+
+        #code
     }
     .to_string();
 
@@ -102,7 +145,7 @@ pub fn test_for() -> std::io::Result<()> {
 
 #[test]
 pub fn test_while() -> std::io::Result<()> {
-    prepare(
+    prepare_mut(
         "WhilePage",
         "while.rs.html",
         quote! {
@@ -332,6 +375,38 @@ pub fn test_component() -> std::io::Result<()> {
     )
 }
 
+#[test]
+pub fn test_slots() -> std::io::Result<()> {
+    prepare(
+        "SlotsPage",
+        "slots.rs.html",
+        quote! {
+            title: String,
+        },
+        quote! {
+            title: "Slots".to_string(),
+        },
+        quote! {},
+    )
+}
+
+#[test]
+pub fn test_props() -> std::io::Result<()> {
+    prepare(
+        "PropsPage",
+        "props.rs.html",
+        quote! {
+            title: String,
+            footer: String,
+        },
+        quote! {
+            title: "Props".to_string(),
+            footer: "bye".to_string(),
+        },
+        quote! {},
+    )
+}
+
 #[test]
 pub fn test_continue_break() -> std::io::Result<()> {
     prepare(
@@ -398,10 +473,204 @@ pub fn test_escaping() -> std::io::Result<()> {
         "escaping.rs.html",
         quote! {
             my_var: String,
+            trusted: rshtml::Markup,
         },
         quote! {
             my_var: "<p>This is <strong>bold</strong> text.</p>".to_string(),
+            trusted: rshtml::Markup("<p>This is <strong>trusted</strong> text.</p>".to_string()),
+        },
+        quote! {},
+    )
+}
+
+#[test]
+pub fn test_flush() -> std::io::Result<()> {
+    prepare(
+        "FlushPage",
+        "flush.rs.html",
+        quote! {
+            title: String,
+            items: Vec<String>,
+        },
+        quote! {
+            title: "Flush".to_string(),
+            items: vec!["a".to_string(), "".to_string()],
         },
         quote! {},
     )
 }
+
+#[test]
+pub fn test_context_escaping() -> std::io::Result<()> {
+    prepare(
+        "ContextEscapingPage",
+        "context_escaping.rs.html",
+        quote! {
+            title: String,
+            link: String,
+            color: String,
+            users: Vec<String>,
+        },
+        quote! {
+            title: "</script><script>alert('x')</script>".to_string(),
+            link: "javascript:alert(1)".to_string(),
+            color: "red; background: url(x)".to_string(),
+            users: vec!["</script>".to_string(), "Bob".to_string()],
+        },
+        quote! {},
+    )
+}
+
+#[test]
+pub fn test_nested() -> std::io::Result<()> {
+    let part_ident = syn::Ident::new("NestedPartPage", Span::call_site());
+    let part_ts = process_template("nested_part.rs.html".to_string(), &part_ident, &syn::Generics::default(), false);
+
+    prepare_with_items(
+        "NestedPage",
+        "nested.rs.html",
+        quote! {
+            title: String,
+            sidebar: NestedPartPage,
+            parts: Vec<NestedPartPage>,
+        },
+        quote! {
+            title: "Nested".to_string(),
+            sidebar: NestedPartPage { title: "<sidebar>".to_string() },
+            parts: vec![NestedPartPage { title: "first".to_string() }, NestedPartPage { title: "second".to_string() }],
+        },
+        quote! {},
+        quote! {
+            struct NestedPartPage {
+                title: String,
+            }
+
+            #part_ts
+        },
+    )
+}
+
+#[test]
+pub fn test_generics() -> std::io::Result<()> {
+    let input: syn::DeriveInput = syn::parse_quote! {
+        struct GenericsPage<'a, T: std::fmt::Display, const N: usize>
+        where
+            T: Clone,
+        {
+            user: &'a User,
+            items: [T; N],
+        }
+    };
+    let ts = process_template("generics.rs.html".to_string(), &input.ident, &input.generics, false);
+
+    pass(quote! {
+        struct User {
+            name: String,
+        }
+
+        #input
+
+        #ts
+
+        fn main() {
+            let user = User { name: "abc".to_string() };
+            let page = GenericsPage { user: &user, items: [1, 2, 3] };
+
+            println!("{}", page.render().unwrap());
+        }
+    })
+}
+
+#[test]
+pub fn test_enum() -> std::io::Result<()> {
+    let input: syn::DeriveInput = syn::parse_quote! {
+        enum State {
+            Loading,
+            Loaded { items: Vec<String>, title: String },
+            Error(String),
+        }
+    };
+    let syn::Data::Enum(data) = &input.data else { unreachable!() };
+
+    let shared_ts = process_template("enum_state.rs.html".to_string(), &input.ident, &input.generics, false);
+
+    let variant_templates = ["enum_loading.rs.html", "enum_loaded.rs.html", "enum_state.rs.html"];
+    let variants = data
+        .variants
+        .iter()
+        .zip(variant_templates)
+        .map(|(variant, path)| (variant, path.to_string().into()))
+        .collect();
+    let variants_ts = process_enum_template(&input.ident, &input.generics, variants, false);
+
+    let main_ts = quote! {
+        fn main() {
+            let states = [
+                State::Loading,
+                State::Loaded { items: vec!["a".to_string()], title: "Items".to_string() },
+                State::Error("<failed>".to_string()),
+            ];
+
+            for state in &states {
+                println!("{}", state.render().unwrap());
+            }
+        }
+    };
+
+    pass(quote! { #input #shared_ts #main_ts })?;
+    pass(quote! { #input #variants_ts #main_ts })
+}
+
+#[test]
+pub fn test_inline_source() -> std::io::Result<()> {
+    let input: syn::DeriveInput = syn::parse_quote! {
+        struct InlinePage {
+            title: String,
+        }
+    };
+    let source: syn::LitStr = syn::parse_quote! { r#"<h1>@self.title</h1>@for i in 0..3 { <i>@i</i> }"# };
+    let ts = process_template(TemplateSource::Inline(source), &input.ident, &input.generics, false);
+
+    let inline_source: syn::LitStr = syn::parse_quote! { r#"<p title="@title">@count</p>"# };
+    let inline_ts = process_inline_template(&inline_source);
+
+    pass(quote! {
+        #input
+
+        #ts
+
+        fn main() {
+            let page = InlinePage { title: "Inline".to_string() };
+            println!("{}", page.render().unwrap());
+
+            let title = "<title>";
+            let count = 3;
+            let widget = #inline_ts;
+            println!("{}", widget.render().unwrap());
+        }
+    })
+}
+
+#[test]
+pub fn test_function_template() -> std::io::Result<()> {
+    let name = syn::Ident::new("row", Span::call_site());
+    let body = process_function_template("function.rs.html".to_string(), &name);
+
+    pass(quote! {
+        fn row<'a>(cells: &'a [String], highlight: bool) -> rshtml::TemplateFn<impl Fn(&mut dyn ::std::fmt::Write, &dyn Fn() -> ::std::fmt::Result) -> ::std::fmt::Result + 'a> #body
+
+        fn main() {
+            let cells = vec!["a".to_string(), "<b>".to_string()];
+            let row = row(&cells, true);
+            println!("{}", row.render().unwrap());
+        }
+    })
+}
+
+#[test]
+pub fn test_partial_tree() {
+    let (ast, diagnostics) = rshtml_core::RsHtmlParser::new().run_partial("recovery.rs.html", rshtml_core::config::Config::default());
+
+    assert_eq!(diagnostics.0.len(), 3);
+    assert!(matches!(&ast, rshtml_core::Node::Template(nodes) if !nodes.is_empty()));
+}
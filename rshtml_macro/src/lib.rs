@@ -1,8 +1,9 @@
 #![doc(hidden)]
 
 use proc_macro::TokenStream;
-use rshtml_core::process_template;
-use syn::{DeriveInput, Expr, Lit, Meta, parse_macro_input};
+use quote::quote;
+use rshtml_core::{TemplateSource, process_enum_template, process_function_template, process_inline_template, process_template};
+use syn::{Data, DeriveInput, Expr, Ident, ItemFn, Lit, LitStr, ReturnType, Type, TypeParamBound, parse_macro_input};
 
 #[proc_macro_derive(RsHtml, attributes(rshtml))]
 pub fn rshtml_derive(input: TokenStream) -> TokenStream {
@@ -10,51 +11,189 @@ pub fn rshtml_derive(input: TokenStream) -> TokenStream {
 
     let struct_name = &input.ident;
 
-    let template_name = match parse_template_path_from_attrs(&input.attrs) {
-        Ok(Some(path)) => path,
-        Ok(None) => {
-            let struct_name_str = struct_name.to_string();
-            let template_file = if let Some(stripped) = struct_name_str.strip_suffix("Page") {
-                format!("{}.rs.html", stripped)
-            } else {
-                format!("{}.rs.html", struct_name_str)
-            };
-
-            template_file.to_lowercase()
-        }
+    let attrs = match parse_attrs(&input.attrs) {
+        Ok(attrs) => attrs,
         Err(err) => {
             return err.to_compile_error().into();
         }
     };
 
-    TokenStream::from(process_template(template_name, struct_name))
+    let template = attrs
+        .template()
+        .unwrap_or_else(|| TemplateSource::Path(default_template_name(struct_name)));
+
+    match &input.data {
+        Data::Struct(_) => TokenStream::from(process_template(template, struct_name, &input.generics, attrs.mutable)),
+        Data::Enum(data) => {
+            let mut variants = Vec::new();
+            let mut has_variant_templates = false;
+
+            for variant in &data.variants {
+                let variant_attrs = match parse_attrs(&variant.attrs) {
+                    Ok(variant_attrs) => variant_attrs,
+                    Err(err) => return err.to_compile_error().into(),
+                };
+
+                if variant_attrs.mutable {
+                    return syn::Error::new_spanned(variant, "`mut` applies to the whole enum, put it on the enum's #[rshtml(...)]")
+                        .to_compile_error()
+                        .into();
+                }
+
+                let variant_template = variant_attrs.template();
+                has_variant_templates |= variant_template.is_some();
+                variants.push((variant, variant_template.unwrap_or_else(|| template.clone())));
+            }
+
+            // without per-variant templates the enum has a single template that matches on `self` itself
+            if !has_variant_templates {
+                return TokenStream::from(process_template(template, struct_name, &input.generics, attrs.mutable));
+            }
+
+            TokenStream::from(process_enum_template(struct_name, &input.generics, variants, attrs.mutable))
+        }
+        Data::Union(_) => syn::Error::new_spanned(struct_name, "RsHtml can't be derived for unions")
+            .to_compile_error()
+            .into(),
+    }
+}
+
+fn default_template_name(name: &Ident) -> String {
+    let name = name.to_string();
+    let template_file = if let Some(stripped) = name.strip_suffix("Page") {
+        format!("{}.rs.html", stripped)
+    } else {
+        format!("{}.rs.html", name)
+    };
+
+    template_file.to_lowercase()
+}
+
+/// Compiles an inline template into a [`Render`](rshtml_core::traits::Render) value, borrowing the local variables it uses.
+///
+/// ```ignore
+/// let name = "world";
+/// let hello = rshtml!(r#"<p>Hello @name</p>"#);
+/// assert_eq!(hello.render().unwrap(), "<p>Hello world</p>");
+/// ```
+#[proc_macro]
+pub fn rshtml(input: TokenStream) -> TokenStream {
+    let source = parse_macro_input!(input as LitStr);
+
+    TokenStream::from(process_inline_template(&source))
+}
+
+/// Turns a function into a template whose arguments are in scope instead of `self`.
+///
+/// The body is generated, so it must be empty, and the return type must be `impl Render` or left out: the function returns
+/// a [`TemplateFn`](rshtml_core::template_fn::TemplateFn), which implements [`Render`](rshtml_core::traits::Render) and can be
+/// rendered on its own with `render()`.
+///
+/// ```ignore
+/// #[template("user_row.rs.html")]
+/// fn user_row(user: &User, highlight: bool) -> impl Render {}
+/// ```
+#[proc_macro_attribute]
+pub fn template(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(attr as LitStr);
+    let mut function = parse_macro_input!(item as ItemFn);
+
+    if !function.block.stmts.is_empty() {
+        return syn::Error::new_spanned(
+            &function.block,
+            "the body of a #[template] function is generated from its template, leave it empty `{}`",
+        )
+        .to_compile_error()
+        .into();
+    }
+    if let ReturnType::Type(_, ty) = &function.sig.output
+        && !returns_impl_render(ty)
+    {
+        return syn::Error::new_spanned(
+            ty,
+            "a #[template] function returns `impl Render`, write that or leave the return type out",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    function.sig.output = syn::parse_quote! {
+        -> rshtml::TemplateFn<impl Fn(&mut dyn ::std::fmt::Write, &dyn Fn() -> ::std::fmt::Result) -> ::std::fmt::Result>
+    };
+
+    let body = process_function_template(path.value(), &function.sig.ident);
+    let (attrs, vis, sig) = (&function.attrs, &function.vis, &function.sig);
+
+    TokenStream::from(quote! {
+        #(#attrs)*
+        #vis #sig #body
+    })
+}
+
+/// Whether `ty` is `impl Render`, with the trait's path written in any form, like `impl rshtml::traits::Render`.
+fn returns_impl_render(ty: &Type) -> bool {
+    let Type::ImplTrait(impl_trait) = ty else {
+        return false;
+    };
+
+    match impl_trait.bounds.iter().collect::<Vec<_>>().as_slice() {
+        [TypeParamBound::Trait(bound)] => bound.path.segments.last().is_some_and(|segment| segment.ident == "Render"),
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct Attrs {
+    path: Option<String>,
+    source: Option<LitStr>,
+    mutable: bool,
+}
+
+impl Attrs {
+    fn template(&self) -> Option<TemplateSource> {
+        match (&self.source, &self.path) {
+            (Some(source), _) => Some(TemplateSource::Inline(source.clone())),
+            (None, Some(path)) => Some(TemplateSource::Path(path.clone())),
+            (None, None) => None,
+        }
+    }
 }
 
-fn parse_template_path_from_attrs(attrs: &[syn::Attribute]) -> syn::Result<Option<String>> {
+fn parse_attrs(attrs: &[syn::Attribute]) -> syn::Result<Attrs> {
+    let mut result = Attrs::default();
+
     for attr in attrs {
         if attr.path().is_ident("rshtml") {
-            return match attr.parse_args::<Meta>() {
-                Ok(Meta::NameValue(name_value)) => {
-                    if name_value.path.is_ident("path") {
-                        if let Expr::Lit(ref expr_lit) = name_value.value {
-                            if let Lit::Str(lit_str) = &expr_lit.lit {
-                                return Ok(Some(lit_str.value()));
-                            }
-                        }
-
-                        Err(syn::Error::new_spanned(
-                            name_value.value,
-                            "Expected a string literal for the `path` argument, e.g., path = \"...\"",
-                        ))
-                    } else {
-                        Err(syn::Error::new_spanned(name_value.path, "Expected argument name `path`, e.g., path = \"...\""))
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("path") {
+                    let value: Expr = meta.value()?.parse()?;
+                    if let Expr::Lit(ref expr_lit) = value
+                        && let Lit::Str(lit_str) = &expr_lit.lit
+                    {
+                        result.path = Some(lit_str.value());
+                        return Ok(());
                     }
+
+                    Err(syn::Error::new_spanned(
+                        value,
+                        "Expected a string literal for the `path` argument, e.g., path = \"...\"",
+                    ))
+                } else if meta.path.is_ident("source") {
+                    result.source = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("mut") {
+                    result.mutable = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("Expected `path = \"...\"`, `source = \"...\"` or `mut` inside #[rshtml(...)]"))
                 }
-                Ok(_) => Err(syn::Error::new_spanned(attr, "Expected `path = \"...\"` inside #[rshtml(...)]")),
-                Err(parse_err) => Err(parse_err),
-            };
+            })?;
+
+            if result.path.is_some() && result.source.is_some() {
+                return Err(syn::Error::new_spanned(attr, "`path` and `source` can't be used together"));
+            }
         }
     }
 
-    Ok(None)
+    Ok(result)
 }
@@ -0,0 +1,51 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use rshtml::RsHtml;
+use std::fmt::Write;
+use std::hint::black_box;
+
+#[derive(RsHtml)]
+struct BenchPage {
+    title: String,
+    items: Vec<Item>,
+}
+
+struct Item {
+    id: usize,
+    name: String,
+}
+
+fn render(c: &mut Criterion) {
+    let page = BenchPage {
+        title: "Benchmark <page>".to_string(),
+        items: (0..100)
+            .map(|id| Item {
+                id,
+                name: format!("Item & {}", id),
+            })
+            .collect(),
+    };
+
+    let mut group = c.benchmark_group("render");
+
+    group.bench_function("generic", |b| {
+        b.iter(|| {
+            let mut buf = String::with_capacity(8192);
+            rshtml::traits::RsHtml::fmt_into(black_box(&page), &mut buf, &|| Ok(())).unwrap();
+            buf
+        })
+    });
+
+    group.bench_function("dyn", |b| {
+        b.iter(|| {
+            let mut buf = String::with_capacity(8192);
+            let writer: &mut dyn Write = &mut buf;
+            rshtml::traits::RsHtml::fmt(black_box(&page), writer).unwrap();
+            buf
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -25,6 +25,39 @@ pub struct User {
     pub age: usize,
 }
 
+#[derive(RsHtml)]
+pub struct StreamedPage {
+    pub title: String,
+    pub items: Vec<String>,
+}
+
+#[derive(RsHtml)]
+#[rshtml(source = r#"<main>@self.page</main>"#)]
+pub struct StreamedLayout {
+    pub page: StreamedPage,
+}
+
+#[derive(RsHtml)]
+#[rshtml(path = "status.rs.html")]
+pub enum Status<'a> {
+    #[rshtml(path = "status_loading.rs.html")]
+    Loading,
+    #[rshtml(path = "status_loaded.rs.html")]
+    Loaded {
+        users: &'a [User],
+    },
+    Failed(String),
+}
+
+#[derive(RsHtml)]
+#[rshtml(source = r#"<span class="badge">@self.count</span>"#)]
+pub struct Badge {
+    pub count: usize,
+}
+
+#[rshtml::template("user_row.rs.html")]
+pub fn user_row(user: &User, highlight: bool) -> impl rshtml::traits::Render {}
+
 impl HomePage {
     fn my_func(&self) -> String {
         format!("{} {}", self.abc, self.def)
@@ -39,7 +72,6 @@ impl HomePage {
 mod tests {
     use super::*;
     use chrono::prelude::*;
-    use pest::Parser;
     use rshtml::traits::RsHtml;
     use std::fs;
     use syn::__private::Span;
@@ -65,7 +97,7 @@ mod tests {
             },
         ];
 
-        let mut homepage = HomePage {
+        let homepage = HomePage {
             title: "Hello".to_string(),
             content: "World".to_string(),
             card_count: 1,
@@ -82,4 +114,305 @@ mod tests {
 
         print!("{}", s);
     }
+
+    #[test]
+    fn test_enum() {
+        let users = [User {
+            name: "abc".to_string(),
+            age: 10,
+        }];
+
+        assert_eq!(Status::Loading.render().unwrap().trim(), "<p class=\"loading\">Loading...</p>");
+        assert_eq!(Status::Loaded { users: &users }.render().unwrap().trim(), "<ul><li>abc</li></ul>");
+        assert_eq!(
+            Status::Failed("<oops>".to_string()).render().unwrap().trim(),
+            "<p class=\"error\">&lt;oops&gt;</p>"
+        );
+    }
+
+    #[test]
+    fn test_inline_templates() {
+        assert_eq!(Badge { count: 3 }.render().unwrap(), "<span class=\"badge\">3</span>");
+
+        let name = "<world>";
+        let badge = Badge { count: 1 };
+        let hello = rshtml::rshtml!(r#"<p>Hello @name</p>@badge"#);
+        assert_eq!(hello.render().unwrap(), "<p>Hello &lt;world&gt;</p><span class=\"badge\">1</span>");
+
+        let mut bytes = Vec::new();
+        hello.write_to_io(&mut bytes).unwrap();
+        assert_eq!(bytes, hello.render_bytes().unwrap());
+
+        let page = rshtml::rshtml!(r#"<div>@hello</div>"#);
+        assert_eq!(
+            page.render().unwrap(),
+            "<div><p>Hello &lt;world&gt;</p><span class=\"badge\">1</span></div>"
+        );
+    }
+
+    #[test]
+    fn test_block_parameters() {
+        let name = "<x>";
+        let page = rshtml::rshtml!(
+            r#"@use "Card.rs.html"
+<Card title={ <b>@name</b> } footer="<i>">body</Card>"#
+        );
+
+        let html = page.render().unwrap();
+        assert!(html.contains("<b>&lt;x&gt;</b>"), "{}", html);
+        assert!(html.contains("&lt;i&gt;"), "{}", html);
+    }
+
+    #[test]
+    fn test_slots() {
+        let title = "<Slots>";
+        let page = rshtml::rshtml!(
+            r#"@use "Panel.rs.html"
+<Panel><slot:footer>by @title</slot:footer>body</Panel>"#
+        );
+
+        let html = page.render().unwrap();
+        assert!(!html.contains("panel-header"));
+        assert!(html.contains("<div class=\"panel-body\">body</div>"));
+        assert!(html.contains("by &lt;Slots&gt;"));
+
+        let page = rshtml::rshtml!(
+            r#"@use "Panel.rs.html"
+<Panel><slot:header><h2>@title</h2></slot:header></Panel>"#
+        );
+
+        assert!(
+            page.render()
+                .unwrap()
+                .contains("<div class=\"panel-header\"><h2>&lt;Slots&gt;</h2></div>")
+        );
+    }
+
+    #[test]
+    fn test_component_scope() {
+        let footer = "outer";
+        let page = rshtml::rshtml!(
+            r#"@use "Card.rs.html"
+<Card title="title" footer="inner">@footer</Card>"#
+        );
+
+        let html = page.render().unwrap();
+        assert!(html.contains("outer"), "{}", html);
+        assert!(html.contains("inner"), "{}", html);
+
+        let page = rshtml::rshtml!(
+            r#"@use "Toggle.rs.html"
+<Toggle on=true/><Toggle on=false/>"#
+        );
+
+        assert_eq!(page.render().unwrap(), "<b>on</b><i>off</i>");
+
+        // a default sees the props before it, not the caller's variable of the same name
+        let a = "caller";
+        let page = rshtml::rshtml!(
+            r#"@use "Def.rs.html"
+@a <Def a="prop"/>"#
+        );
+
+        assert_eq!(page.render().unwrap(), "caller \nprop/prop");
+    }
+
+    #[test]
+    fn test_untyped_components() {
+        // without @props, the parameters take the types of the values given
+        let items = ["<a>", "b"];
+        let page = rshtml::rshtml!(
+            r#"@use "List.rs.html"
+<List items=@items.iter() count=@items.len()/>"#
+        );
+
+        assert_eq!(page.render().unwrap(), "<ul><li>&lt;a&gt;</li><li>b</li></ul>\n<p>2 items</p>");
+    }
+
+    #[test]
+    fn test_shared_components() {
+        let label = "<on>";
+        let page = rshtml::rshtml!(
+            r#"@use "Switch.rs.html"
+@for on in [true, false, true] {<Switch on=@on/>}
+<p title="@label"><Switch on=false/></p>"#
+        );
+
+        let shared = page.render().unwrap();
+        assert_eq!(shared, "\n<b>on</b>\n<i>off</i>\n<b>on</b><p title=\"&lt;on&gt;\">\n<i>off</i></p>");
+
+        let page = rshtml::rshtml!(
+            r#"@use inline "Switch.rs.html"
+@for on in [true, false, true] {<Switch on=@on/>}
+<p title="@label"><Switch on=false/></p>"#
+        );
+
+        assert_eq!(page.render().unwrap(), shared);
+
+        // the function shared in html text isn't used in a <script>, where the name is written as a JS string
+        let name = "</script>";
+        let page = rshtml::rshtml!(
+            r#"@use "Greet.rs.html"
+<p><Greet name=@name/></p><script><Greet name=@name/></script>"#
+        );
+
+        assert_eq!(
+            page.render().unwrap(),
+            "<p>\nhello(&lt;&#x2F;script&gt;);</p><script>\nhello(\"\\u003C/script\\u003E\");</script>"
+        );
+    }
+
+    #[test]
+    fn test_component_contexts() {
+        // the child content is escaped for the attribute the component writes it into
+        let url = "JavaScript:alert(1)";
+        let page = rshtml::rshtml!(
+            r#"@use "Link.rs.html"
+<Link label="home">@url</Link>"#
+        );
+
+        assert_eq!(page.render().unwrap(), "\n<a href=\"about:invalid#rshtml-unsafe-url\">home</a>");
+
+        // the caller goes on in the <script> the component leaves open
+        let name = "</script>";
+        let page = rshtml::rshtml!(
+            r#"@use "Script.rs.html"
+<Script name=@name/> var other = @name;</script>"#
+        );
+
+        assert_eq!(
+            page.render().unwrap(),
+            "\n<script>var name = \"\\u003C\\u002Fscript\\u003E\"; var other = \"\\u003C/script\\u003E\";</script>"
+        );
+    }
+
+    #[test]
+    fn test_render_dispatch() {
+        struct Name(&'static str);
+
+        impl Name {
+            fn render_to(&self, f: &mut dyn Write, _: rshtml::escape::Context) -> std::fmt::Result {
+                f.write_str(self.0)
+            }
+        }
+
+        impl std::fmt::Display for Name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.0)
+            }
+        }
+
+        let name = Name("<b>");
+        let page = rshtml::rshtml!("<p>@name</p>");
+
+        assert_eq!(page.render().unwrap(), "<p>&lt;b&gt;</p>");
+    }
+
+    #[test]
+    fn test_rust_scanning() {
+        struct Point {
+            x: usize,
+        }
+        let items = vec![1, 2, 3];
+
+        let page = rshtml::rshtml!(
+            r####"@if items.len() == (Point { x: 3 }).x {<p>three</p>}
+@for n in items.iter().filter(|n| { **n != 2 }) {<b>@n</b>}
+@match items.first() { Some(n @ 1..=5) if *n != '}' as i32 => {<i>small</i>} _ => {big} }
+@{ fn first<'a>(items: &'a [i32]) -> &'a i32 { &items[0] } let brace = '}'; let raw = r##"a"#}"##; /* } */ }
+@first(&items) @brace @raw"####
+        );
+
+        assert_eq!(page.render().unwrap(), "<p>three</p><b>1</b><b>3</b><i>small</i>\n\n1 } a&quot;#}");
+
+        let points = vec![Point { x: 2 }, Point { x: 3 }];
+        let page = rshtml::rshtml!(
+            r#"@if let Point { x: 1 } = points[0] {<p>one</p>} else if let Point { x } = points[1] {<p>@x</p>}
+@for Point { x } in &points {<b>@x</b>}
+@{ let mut n = 0; }@while let Point { x: 0..=1 } = (Point { x: n }) {<i>@n</i>@{ n += 1; }}"#
+        );
+
+        assert_eq!(page.render().unwrap(), "<p>3</p>\n<b>2</b><b>3</b><i>0</i><i>1</i>");
+    }
+
+    #[test]
+    fn test_function_template() {
+        let user = User {
+            name: "<abc>".to_string(),
+            age: 10,
+        };
+
+        assert_eq!(user_row(&user, true).render().unwrap(), "<li class=\"active\">&lt;abc&gt; (10)</li>");
+
+        let list = rshtml::rshtml!(r#"<ul>@user_row(&user, false)</ul>"#);
+        assert_eq!(list.render().unwrap(), "<ul><li class=\"user\">&lt;abc&gt; (10)</li></ul>");
+    }
+
+    #[tokio::test]
+    async fn test_render_stream() {
+        use tokio_stream::StreamExt;
+
+        let page = StreamedPage {
+            title: "Streamed".to_string(),
+            items: vec!["one".to_string(), "two".to_string()],
+        };
+
+        let chunks: Vec<_> = page.render_stream().map(|chunk| chunk.unwrap()).collect().await;
+
+        assert_eq!(chunks.len(), 2);
+        let head = std::str::from_utf8(&chunks[0]).unwrap();
+        let body = std::str::from_utf8(&chunks[1]).unwrap();
+        assert!(head.contains("<title>Streamed</title>") && !head.contains("<body>"));
+        assert!(body.contains("<p>one</p>") && body.contains("<p>two</p>"));
+    }
+
+    #[tokio::test]
+    async fn test_render_stream_nested() {
+        use std::cell::Cell;
+        use tokio_stream::StreamExt;
+
+        let layout = StreamedLayout {
+            page: StreamedPage {
+                title: "Nested".to_string(),
+                items: vec!["one".to_string()],
+            },
+        };
+
+        let chunks: Vec<_> = layout.render_stream().map(|chunk| chunk.unwrap()).collect().await;
+
+        assert_eq!(chunks.len(), 2);
+        assert!(std::str::from_utf8(&chunks[0]).unwrap().starts_with("<main>"));
+        assert!(std::str::from_utf8(&chunks[1]).unwrap().ends_with("</main>"));
+
+        let flushes = Cell::new(0);
+        let part = rshtml::rshtml!("<i>a</i>@flush <i>b</i>");
+        let page = rshtml::rshtml!("<p>@part</p>");
+
+        let mut html = String::new();
+        rshtml::traits::Render::render_with_flush(&page, &mut html, rshtml::escape::Context::Html, &|| {
+            flushes.set(flushes.get() + 1);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(html, "<p><i>a</i> <i>b</i></p>");
+        assert_eq!(flushes.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_render_stream_panic() {
+        use tokio_stream::StreamExt;
+
+        #[derive(RsHtml)]
+        #[rshtml(source = "<p>head</p>@flush <p>@self.items[0]</p>")]
+        struct Broken {
+            items: Vec<String>,
+        }
+
+        let chunks: Vec<_> = Broken { items: Vec::new() }.render_stream().collect().await;
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].as_deref().unwrap(), b"<p>head</p>");
+        assert!(chunks[1].is_err());
+    }
 }
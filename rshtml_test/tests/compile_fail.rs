@@ -0,0 +1,7 @@
+/// Templates whose Rust code doesn't compile, with the errors rustc reports for them in `ui/*.stderr`.
+/// Run with `TRYBUILD=overwrite` to write the errors again after a change to the generated code.
+#[test]
+fn test_compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
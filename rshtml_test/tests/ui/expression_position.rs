@@ -0,0 +1,20 @@
+use rshtml::RsHtml;
+use rshtml::traits::RsHtml as _;
+
+// rustc can't point into the template, its errors name the macros wrapping the expressions after their line and column
+#[derive(RsHtml)]
+#[rshtml(source = "<h1>@self.title</h1>
+<p>@self.missing_field</p>
+@for item in &self.itemz {<i>@item</i>}")]
+struct Page {
+    title: String,
+    items: Vec<String>,
+}
+
+fn main() {
+    let page = Page {
+        title: "title".to_string(),
+        items: Vec::new(),
+    };
+    println!("{}", page.render().unwrap());
+}